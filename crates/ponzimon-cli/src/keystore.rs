@@ -0,0 +1,482 @@
+//! Passphrase-encrypted keystore for operator wallets.
+//!
+//! The file is JSON. A 32-byte key is derived from the passphrase with
+//! Argon2id (parameters and salt stored in the file), and every secret key is
+//! sealed separately with XChaCha20-Poly1305 under a random nonce, using the
+//! wallet's public key as associated data so entries can't be swapped between
+//! records. A sealed verifier lets a wrong passphrase fail up front, even for
+//! an empty keystore.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use zeroize::Zeroizing;
+
+const KEYSTORE_VERSION: u32 = 1;
+const VERIFIER_PLAINTEXT: &[u8] = b"ponzimon-keystore";
+const VERIFIER_AAD: &[u8] = b"verifier";
+
+#[derive(Serialize, Deserialize)]
+struct KeystoreFile {
+    version: u32,
+    kdf: KdfParams,
+    verifier: Sealed,
+    wallets: Vec<WalletEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct KdfParams {
+    m_cost_kib: u32,
+    t_cost: u32,
+    p_cost: u32,
+    #[serde(with = "b64_array")]
+    salt: [u8; 16],
+}
+
+impl Default for KdfParams {
+    /// OWASP's recommended Argon2id baseline (19 MiB, 2 passes, 1 lane).
+    fn default() -> Self {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            m_cost_kib: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+            salt,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Sealed {
+    #[serde(with = "b64_vec")]
+    nonce: Vec<u8>,
+    #[serde(with = "b64_vec")]
+    ciphertext: Vec<u8>,
+}
+
+/// Public metadata of a stored wallet. The secret key never leaves `sealed`
+/// unencrypted except through `Keystore::keypair`/`export_base58`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WalletEntry {
+    pub pubkey: String,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    sealed: Sealed,
+}
+
+/// Selects wallets by group and/or tags. Empty fields match everything.
+#[derive(Debug, Default, Clone)]
+pub struct WalletFilter {
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+}
+
+impl WalletFilter {
+    fn matches(&self, entry: &WalletEntry) -> bool {
+        self.group
+            .as_ref()
+            .is_none_or(|group| entry.group.as_ref() == Some(group))
+            && self.tags.iter().all(|tag| entry.tags.contains(tag))
+    }
+}
+
+pub struct Keystore {
+    path: PathBuf,
+    file: KeystoreFile,
+    cipher: XChaCha20Poly1305,
+}
+
+impl Keystore {
+    /// Creates a new, empty keystore. Refuses to overwrite an existing file.
+    pub fn create(path: &Path, passphrase: &str) -> Result<Self> {
+        Self::create_with_kdf(path, passphrase, KdfParams::default())
+    }
+
+    fn create_with_kdf(path: &Path, passphrase: &str, kdf: KdfParams) -> Result<Self> {
+        if path.exists() {
+            bail!("{} already exists", path.display());
+        }
+        let cipher = derive_cipher(passphrase, &kdf)?;
+        let verifier = seal(&cipher, VERIFIER_PLAINTEXT, VERIFIER_AAD)?;
+
+        let keystore = Self {
+            path: path.to_path_buf(),
+            file: KeystoreFile {
+                version: KEYSTORE_VERSION,
+                kdf,
+                verifier,
+                wallets: Vec::new(),
+            },
+            cipher,
+        };
+        keystore.save()?;
+        Ok(keystore)
+    }
+
+    pub fn open(path: &Path, passphrase: &str) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let file: KeystoreFile =
+            serde_json::from_slice(&data).with_context(|| format!("parsing {}", path.display()))?;
+        if file.version != KEYSTORE_VERSION {
+            bail!("unsupported keystore version {}", file.version);
+        }
+
+        let cipher = derive_cipher(passphrase, &file.kdf)?;
+        let verifier =
+            open_sealed(&cipher, &file.verifier, VERIFIER_AAD).context("wrong passphrase")?;
+        if verifier.as_slice() != VERIFIER_PLAINTEXT {
+            bail!("wrong passphrase");
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            cipher,
+        })
+    }
+
+    /// Writes the keystore atomically (temp file + rename), owner-readable only.
+    pub fn save(&self) -> Result<()> {
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.file)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[WalletEntry] {
+        &self.file.wallets
+    }
+
+    /// Imports a base58-encoded 64-byte secret key, the format the browser
+    /// ImportWallet dialog accepts. Re-importing a known wallet only merges
+    /// its group and tags.
+    pub fn import_base58(
+        &mut self,
+        secret: &str,
+        group: Option<String>,
+        tags: &[String],
+    ) -> Result<Pubkey> {
+        let bytes = Zeroizing::new(
+            bs58::decode(secret.trim())
+                .into_vec()
+                .context("secret key is not valid base58")?,
+        );
+        let keypair = Keypair::from_bytes(&bytes).context("secret key must be 64 bytes")?;
+        let pubkey = keypair.pubkey();
+
+        if let Some(entry) = self.entry_mut(&pubkey) {
+            if group.is_some() {
+                entry.group = group;
+            }
+            entry.tags.extend(tags.iter().cloned());
+            return Ok(pubkey);
+        }
+
+        let sealed = seal(&self.cipher, &bytes, pubkey.as_ref())?;
+        self.file.wallets.push(WalletEntry {
+            pubkey: pubkey.to_string(),
+            group,
+            tags: tags.iter().cloned().collect(),
+            sealed,
+        });
+        Ok(pubkey)
+    }
+
+    /// Returns the wallet's secret key in the same base58 format `import_base58` takes.
+    pub fn export_base58(&self, pubkey: &Pubkey) -> Result<Zeroizing<String>> {
+        let keypair = self.keypair(pubkey)?;
+        Ok(Zeroizing::new(
+            bs58::encode(keypair.to_bytes()).into_string(),
+        ))
+    }
+
+    pub fn keypair(&self, pubkey: &Pubkey) -> Result<Keypair> {
+        let entry = self
+            .file
+            .wallets
+            .iter()
+            .find(|entry| entry.pubkey == pubkey.to_string())
+            .with_context(|| format!("{pubkey} is not in the keystore"))?;
+        self.decrypt(entry)
+    }
+
+    /// Decrypts every wallet matching `filter`, in keystore order.
+    pub fn keypairs(&self, filter: &WalletFilter) -> Result<Vec<Keypair>> {
+        self.file
+            .wallets
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .map(|entry| self.decrypt(entry))
+            .collect()
+    }
+
+    pub fn set_group(&mut self, pubkey: &Pubkey, group: Option<String>) -> Result<()> {
+        self.require_entry(pubkey)?.group = group;
+        Ok(())
+    }
+
+    pub fn add_tags(&mut self, pubkey: &Pubkey, tags: &[String]) -> Result<()> {
+        self.require_entry(pubkey)?
+            .tags
+            .extend(tags.iter().cloned());
+        Ok(())
+    }
+
+    pub fn remove_tags(&mut self, pubkey: &Pubkey, tags: &[String]) -> Result<()> {
+        let entry = self.require_entry(pubkey)?;
+        for tag in tags {
+            entry.tags.remove(tag);
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, pubkey: &Pubkey) -> Result<()> {
+        let before = self.file.wallets.len();
+        self.file
+            .wallets
+            .retain(|entry| entry.pubkey != pubkey.to_string());
+        if self.file.wallets.len() == before {
+            bail!("{pubkey} is not in the keystore");
+        }
+        Ok(())
+    }
+
+    fn entry_mut(&mut self, pubkey: &Pubkey) -> Option<&mut WalletEntry> {
+        let pubkey = pubkey.to_string();
+        self.file
+            .wallets
+            .iter_mut()
+            .find(|entry| entry.pubkey == pubkey)
+    }
+
+    fn require_entry(&mut self, pubkey: &Pubkey) -> Result<&mut WalletEntry> {
+        self.entry_mut(pubkey)
+            .with_context(|| format!("{pubkey} is not in the keystore"))
+    }
+
+    fn decrypt(&self, entry: &WalletEntry) -> Result<Keypair> {
+        let pubkey: Pubkey = entry.pubkey.parse()?;
+        let bytes = open_sealed(&self.cipher, &entry.sealed, pubkey.as_ref())
+            .with_context(|| format!("decrypting {pubkey}"))?;
+        let keypair = Keypair::from_bytes(&bytes)?;
+        if keypair.pubkey() != pubkey {
+            bail!("keystore entry {pubkey} holds a different key");
+        }
+        Ok(keypair)
+    }
+}
+
+fn derive_cipher(passphrase: &str, kdf: &KdfParams) -> Result<XChaCha20Poly1305> {
+    let params = Params::new(kdf.m_cost_kib, kdf.t_cost, kdf.p_cost, Some(32))
+        .map_err(|e| anyhow::anyhow!("invalid argon2 parameters: {e}"))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &kdf.salt, key.as_mut())
+        .map_err(|e| anyhow::anyhow!("key derivation failed: {e}"))?;
+    Ok(XChaCha20Poly1305::new(&Key::from(*key)))
+}
+
+fn seal(cipher: &XChaCha20Poly1305, plaintext: &[u8], aad: &[u8]) -> Result<Sealed> {
+    let mut nonce = [0u8; 24];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| anyhow::anyhow!("encryption failed"))?;
+    Ok(Sealed {
+        nonce: nonce.to_vec(),
+        ciphertext,
+    })
+}
+
+fn open_sealed(
+    cipher: &XChaCha20Poly1305,
+    sealed: &Sealed,
+    aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    let nonce: [u8; 24] = sealed
+        .nonce
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("corrupt nonce"))?;
+    cipher
+        .decrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: &sealed.ciphertext,
+                aad,
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| anyhow::anyhow!("authentication failed"))
+}
+
+mod b64_vec {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+mod b64_array {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 16], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 16], D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        BASE64
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)?
+            .try_into()
+            .map_err(|_| serde::de::Error::custom("expected 16 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Cheap Argon2 parameters so the tests don't spend seconds per derivation.
+    fn test_kdf() -> KdfParams {
+        KdfParams {
+            m_cost_kib: 64,
+            t_cost: 1,
+            ..KdfParams::default()
+        }
+    }
+
+    /// A fresh path under the system temp dir; removed again on drop.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new() -> Self {
+            static COUNTER: AtomicU32 = AtomicU32::new(0);
+            let name = format!(
+                "ponzimon-keystore-test-{}-{}.json",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            );
+            Self(std::env::temp_dir().join(name))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn roundtrips_keys_through_the_file() {
+        let path = TempPath::new();
+        let wallet = Keypair::new();
+        let secret = bs58::encode(wallet.to_bytes()).into_string();
+
+        let mut keystore = Keystore::create_with_kdf(&path.0, "hunter2", test_kdf()).unwrap();
+        let pubkey = keystore
+            .import_base58(&secret, Some("fleet".to_string()), &["a".to_string()])
+            .unwrap();
+        assert_eq!(pubkey, wallet.pubkey());
+        keystore.save().unwrap();
+
+        // The secret key never hits the file in the clear
+        let contents = std::fs::read_to_string(&path.0).unwrap();
+        assert!(!contents.contains(&secret));
+
+        let keystore = Keystore::open(&path.0, "hunter2").unwrap();
+        assert_eq!(
+            keystore.keypair(&pubkey).unwrap().to_bytes(),
+            wallet.to_bytes()
+        );
+        assert_eq!(keystore.export_base58(&pubkey).unwrap().as_str(), secret);
+
+        let filter = WalletFilter {
+            group: Some("fleet".to_string()),
+            tags: vec!["a".to_string()],
+        };
+        let keypairs = keystore.keypairs(&filter).unwrap();
+        assert_eq!(keypairs.len(), 1);
+        assert_eq!(keypairs[0].pubkey(), pubkey);
+    }
+
+    #[test]
+    fn rejects_a_wrong_passphrase_via_the_verifier() {
+        let path = TempPath::new();
+        // An empty keystore has no wallet to fail on, only the verifier
+        Keystore::create_with_kdf(&path.0, "correct horse", test_kdf()).unwrap();
+
+        let error = Keystore::open(&path.0, "battery staple").err().unwrap();
+        assert!(format!("{error:#}").contains("wrong passphrase"));
+        assert!(Keystore::open(&path.0, "correct horse").is_ok());
+    }
+
+    #[test]
+    fn rejects_entries_swapped_between_wallets() {
+        let path = TempPath::new();
+        let first = Keypair::new();
+        let second = Keypair::new();
+
+        let mut keystore = Keystore::create_with_kdf(&path.0, "pw", test_kdf()).unwrap();
+        for wallet in [&first, &second] {
+            keystore
+                .import_base58(&bs58::encode(wallet.to_bytes()).into_string(), None, &[])
+                .unwrap();
+        }
+
+        // The pubkey is the associated data, so a sealed key moved to another
+        // record fails authentication instead of yielding the wrong wallet
+        keystore.file.wallets[0].sealed = keystore.file.wallets[1].sealed.clone();
+        let error = keystore.keypair(&first.pubkey()).err().unwrap();
+        assert!(format!("{error:#}").contains("authentication failed"));
+        assert!(keystore.keypair(&second.pubkey()).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saves_owner_readable_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = TempPath::new();
+        let keystore = Keystore::create_with_kdf(&path.0, "pw", test_kdf()).unwrap();
+        keystore.save().unwrap();
+
+        let mode = std::fs::metadata(&path.0).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,372 @@
+//! `ponzimon` — runs one operation across a fleet of player wallets, loaded
+//! from a keypair directory or an encrypted keystore.
+
+mod keystore;
+mod ops;
+mod report;
+mod rpc;
+mod wallets;
+
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use futures::stream::{self, StreamExt};
+use ponzimon_client::CraftTarget;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::keystore::{Keystore, WalletFilter};
+use crate::ops::Operation;
+use crate::rpc::Rpc;
+
+#[derive(Parser)]
+#[command(
+    name = "ponzimon",
+    about = "Batch operations for ponzimon player wallets"
+)]
+struct Cli {
+    #[arg(
+        long,
+        env = "PONZIMON_RPC_URL",
+        default_value = "https://api.mainnet-beta.solana.com"
+    )]
+    rpc_url: String,
+    #[arg(long, env = "PONZIMON_PROGRAM_ID")]
+    program_id: Option<Pubkey>,
+    #[arg(long, env = "PONZIMON_TOKEN_MINT")]
+    token_mint: Option<Pubkey>,
+    /// Directory of Solana CLI keypair files (*.json), one per player wallet
+    #[arg(long, env = "PONZIMON_KEYPAIR_DIR", conflicts_with = "keystore")]
+    keypair_dir: Option<PathBuf>,
+    /// Encrypted keystore file; passphrase from PONZIMON_KEYSTORE_PASSPHRASE or a prompt
+    #[arg(long, env = "PONZIMON_KEYSTORE")]
+    keystore: Option<PathBuf>,
+    /// Only use keystore wallets in this group
+    #[arg(long, requires = "keystore")]
+    group: Option<String>,
+    /// Only use keystore wallets carrying this tag (repeatable; all must match)
+    #[arg(long = "tag", requires = "keystore")]
+    tags: Vec<String>,
+    /// Number of wallets processed in parallel
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    /// Attempts per transaction before a wallet is marked failed
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+    #[arg(long, default_value_t = 1000)]
+    retry_delay_ms: u64,
+    #[arg(long, default_value_t = 400_000)]
+    compute_unit_limit: u32,
+    /// Priority fee in micro-lamports per compute unit
+    #[arg(long, default_value_t = 200)]
+    compute_unit_price: u64,
+    /// Write one `<wallet>.json` report per wallet here instead of printing a JSON array
+    #[arg(long)]
+    report_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Buy the starter farm
+    PurchaseInitialFarm {
+        #[arg(long)]
+        referrer: Option<Pubkey>,
+    },
+    /// Upgrade the farm through every tier up to --to in one transaction
+    UpgradeFarm {
+        #[arg(long)]
+        to: u8,
+    },
+    /// Stake unstaked cards, best hashpower per berry first, while capacity allows
+    Stake,
+    /// Restake the hashpower-maximizing card subset on-chain (optimize_loadout)
+    OptimizeLoadout,
+    /// Unstake every staked card
+    Unstake,
+    /// open_booster_commit, wait for the reveal slot, then settle_open_booster
+    OpenBooster {
+        #[arg(long, default_value_t = 1)]
+        count: u32,
+        /// Let settle_open_booster stake the drawn cards while capacity allows
+        #[arg(long)]
+        auto_stake: bool,
+    },
+    /// Recycle every unstaked card at or below a rarity (commit + settle)
+    Recycle {
+        #[arg(long)]
+        max_rarity: u8,
+    },
+    /// Discard every unstaked, unlocked card at or below a rarity for shards
+    Discard {
+        #[arg(long)]
+        max_rarity: u8,
+    },
+    /// Spend discard shards on a booster, or on a card of --rarity (commit + settle)
+    Craft {
+        #[arg(long)]
+        rarity: Option<u8>,
+    },
+    /// Claim pending rewards
+    Claim,
+    /// Withdraw the referral commissions escrowed for each wallet
+    ClaimReferralRewards,
+    /// Transfer every game token to a recipient wallet
+    Sweep {
+        #[arg(long)]
+        recipient: Pubkey,
+    },
+    /// Manage the encrypted keystore given by --keystore
+    Keystore {
+        #[command(subcommand)]
+        command: KeystoreCommand,
+    },
+}
+
+#[derive(Subcommand, Clone)]
+enum KeystoreCommand {
+    /// Create an empty keystore
+    Init,
+    /// Import base58 secret keys, one per line (the ImportWallet format)
+    Import {
+        /// Read keys from this file instead of stdin
+        #[arg(long)]
+        file: Option<PathBuf>,
+        #[arg(long)]
+        group: Option<String>,
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// Print base58 secret keys for the selected wallets, one per line
+    Export {
+        /// Export only this wallet; otherwise --group/--tag select wallets
+        #[arg(long)]
+        pubkey: Option<Pubkey>,
+    },
+    /// List wallets with their group and tags
+    List,
+    /// Change a wallet's group and tags
+    Tag {
+        pubkey: Pubkey,
+        /// Move the wallet to this group
+        #[arg(long, conflicts_with = "clear_group")]
+        group: Option<String>,
+        #[arg(long)]
+        clear_group: bool,
+        #[arg(long = "add")]
+        add: Vec<String>,
+        #[arg(long = "remove")]
+        remove: Vec<String>,
+    },
+    /// Delete a wallet from the keystore
+    Remove { pubkey: Pubkey },
+}
+
+impl From<Command> for Operation {
+    fn from(command: Command) -> Self {
+        match command {
+            Command::PurchaseInitialFarm { referrer } => {
+                Operation::PurchaseInitialFarm { referrer }
+            }
+            Command::UpgradeFarm { to } => Operation::UpgradeFarm { to },
+            Command::Stake => Operation::Stake,
+            Command::OptimizeLoadout => Operation::OptimizeLoadout,
+            Command::Unstake => Operation::Unstake,
+            Command::OpenBooster { count, auto_stake } => {
+                Operation::OpenBooster { count, auto_stake }
+            }
+            Command::Recycle { max_rarity } => Operation::Recycle { max_rarity },
+            Command::Discard { max_rarity } => Operation::Discard { max_rarity },
+            Command::Craft { rarity } => Operation::Craft {
+                target: rarity.map_or(CraftTarget::Booster, |rarity| CraftTarget::Card { rarity }),
+            },
+            Command::Claim => Operation::Claim,
+            Command::ClaimReferralRewards => Operation::ClaimReferralRewards,
+            Command::Sweep { recipient } => Operation::Sweep { recipient },
+            Command::Keystore { .. } => unreachable!("keystore commands don't touch the chain"),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if let Command::Keystore { command } = &cli.command {
+        let path = cli
+            .keystore
+            .as_deref()
+            .context("--keystore is required for keystore commands")?;
+        return run_keystore_command(path, command, &wallet_filter(&cli));
+    }
+
+    let wallets = load_wallets(&cli)?;
+    let program_id = cli.program_id.context("--program-id is required")?;
+    let token_mint = cli.token_mint.context("--token-mint is required")?;
+
+    let rpc = Arc::new(
+        Rpc::connect(
+            &cli.rpc_url,
+            program_id,
+            token_mint,
+            rpc::SendConfig {
+                retries: cli.retries.max(1),
+                retry_delay: Duration::from_millis(cli.retry_delay_ms),
+                compute_unit_limit: cli.compute_unit_limit,
+                compute_unit_price: cli.compute_unit_price,
+            },
+        )
+        .await?,
+    );
+    let operation: Operation = cli.command.clone().into();
+
+    let reports: Vec<_> = stream::iter(wallets)
+        .map(|wallet| {
+            let rpc = rpc.clone();
+            let operation = operation.clone();
+            async move { ops::run(&rpc, &wallet, &operation).await }
+        })
+        .buffer_unordered(cli.concurrency.max(1))
+        .collect()
+        .await;
+
+    match &cli.report_dir {
+        Some(dir) => report::write_per_wallet(dir, &reports)
+            .with_context(|| format!("writing reports to {}", dir.display()))?,
+        None => println!("{}", serde_json::to_string_pretty(&reports)?),
+    }
+
+    let failed = reports.iter().filter(|r| !r.ok).count();
+    eprintln!("{} wallets, {} failed", reports.len(), failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn wallet_filter(cli: &Cli) -> WalletFilter {
+    WalletFilter {
+        group: cli.group.clone(),
+        tags: cli.tags.clone(),
+    }
+}
+
+fn load_wallets(cli: &Cli) -> Result<Vec<Keypair>> {
+    let (wallets, source) = match (&cli.keystore, &cli.keypair_dir) {
+        (Some(path), _) => {
+            let keystore = Keystore::open(path, &passphrase(false)?)?;
+            (keystore.keypairs(&wallet_filter(cli))?, path)
+        }
+        (None, Some(dir)) => (wallets::load_keypair_dir(dir)?, dir),
+        (None, None) => anyhow::bail!("pass --keypair-dir or --keystore"),
+    };
+    anyhow::ensure!(
+        !wallets.is_empty(),
+        "no wallets selected from {}",
+        source.display()
+    );
+    Ok(wallets)
+}
+
+fn passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("PONZIMON_KEYSTORE_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("Keystore passphrase: ")?;
+    if confirm {
+        anyhow::ensure!(
+            passphrase == rpassword::prompt_password("Repeat passphrase: ")?,
+            "passphrases don't match"
+        );
+    }
+    Ok(passphrase)
+}
+
+fn run_keystore_command(
+    path: &Path,
+    command: &KeystoreCommand,
+    filter: &WalletFilter,
+) -> Result<()> {
+    if let KeystoreCommand::Init = command {
+        Keystore::create(path, &passphrase(true)?)?;
+        eprintln!("created {}", path.display());
+        return Ok(());
+    }
+
+    let mut keystore = Keystore::open(path, &passphrase(false)?)?;
+    match command {
+        KeystoreCommand::Init => unreachable!(),
+        KeystoreCommand::Import { file, group, tags } => {
+            let lines: Vec<String> = match file {
+                Some(file) => std::fs::read_to_string(file)
+                    .with_context(|| format!("reading {}", file.display()))?
+                    .lines()
+                    .map(str::to_owned)
+                    .collect(),
+                None => std::io::stdin().lock().lines().collect::<Result<_, _>>()?,
+            };
+            let mut imported = 0;
+            for (line_no, line) in lines.iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let pubkey = keystore
+                    .import_base58(line, group.clone(), tags)
+                    .with_context(|| format!("line {}", line_no + 1))?;
+                println!("{pubkey}");
+                imported += 1;
+            }
+            keystore.save()?;
+            eprintln!("imported {imported} wallets");
+        }
+        KeystoreCommand::Export { pubkey } => {
+            let keypairs = match pubkey {
+                Some(pubkey) => vec![keystore.keypair(pubkey)?],
+                None => keystore.keypairs(filter)?,
+            };
+            for keypair in keypairs {
+                println!("{}", *keystore.export_base58(&keypair.pubkey())?);
+            }
+        }
+        KeystoreCommand::List => {
+            let entries: Vec<_> = keystore
+                .entries()
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "pubkey": entry.pubkey,
+                        "group": entry.group,
+                        "tags": entry.tags,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+        KeystoreCommand::Tag {
+            pubkey,
+            group,
+            clear_group,
+            add,
+            remove,
+        } => {
+            if *clear_group {
+                keystore.set_group(pubkey, None)?;
+            } else if group.is_some() {
+                keystore.set_group(pubkey, group.clone())?;
+            }
+            keystore.add_tags(pubkey, add)?;
+            keystore.remove_tags(pubkey, remove)?;
+            keystore.save()?;
+        }
+        KeystoreCommand::Remove { pubkey } => {
+            keystore.remove(pubkey)?;
+            keystore.save()?;
+        }
+    }
+    Ok(())
+}
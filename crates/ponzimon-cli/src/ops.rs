@@ -0,0 +1,324 @@
+use anchor_spl::associated_token::spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use anchor_spl::token::spl_token;
+use anyhow::{bail, Result};
+use ponzimon_client::{
+    CraftTarget, PendingRandomAction, Player, PlayerAction, MAX_ACTIONS_PER_BATCH,
+    MIN_RANDOMNESS_DELAY_SLOTS, OPTIMIZE_LOADOUT_COMPUTE_UNITS,
+};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+
+use crate::report::{StepReport, WalletReport};
+use crate::rpc::Rpc;
+
+#[derive(Debug, Clone)]
+pub enum Operation {
+    PurchaseInitialFarm { referrer: Option<Pubkey> },
+    UpgradeFarm { to: u8 },
+    Stake,
+    OptimizeLoadout,
+    Unstake,
+    OpenBooster { count: u32, auto_stake: bool },
+    Recycle { max_rarity: u8 },
+    Discard { max_rarity: u8 },
+    Craft { target: CraftTarget },
+    Claim,
+    ClaimReferralRewards,
+    Sweep { recipient: Pubkey },
+}
+
+pub async fn run(rpc: &Rpc, wallet: &Keypair, operation: &Operation) -> WalletReport {
+    let mut report = WalletReport::new(wallet.pubkey().to_string());
+    if let Err(e) = run_steps(rpc, wallet, operation, &mut report).await {
+        report.fail(&e);
+    }
+    report
+}
+
+async fn run_steps(
+    rpc: &Rpc,
+    wallet: &Keypair,
+    operation: &Operation,
+    report: &mut WalletReport,
+) -> Result<()> {
+    let owner = wallet.pubkey();
+    let b = &rpc.builder;
+
+    match operation {
+        Operation::PurchaseInitialFarm { referrer } => {
+            if rpc.player(&owner).await?.is_some() {
+                bail!("farm already purchased");
+            }
+            push(
+                report,
+                rpc.send(
+                    wallet,
+                    "purchase_initial_farm",
+                    &[b.purchase_initial_farm(&owner, *referrer)],
+                )
+                .await,
+            )?;
+        }
+        Operation::UpgradeFarm { to } => {
+            let player = require_player(rpc, &owner).await?;
+            if player.farm.farm_type >= *to {
+                return Ok(());
+            }
+            push(
+                report,
+                rpc.send(
+                    wallet,
+                    "upgrade_farm_to",
+                    &[b.upgrade_farm_to(&owner, player.referrer(), *to)],
+                )
+                .await,
+            )?;
+        }
+        Operation::Stake => {
+            let player = require_player(rpc, &owner).await?;
+            let serials = pick_cards_to_stake(&player);
+            for chunk in serials.chunks(MAX_ACTIONS_PER_BATCH) {
+                let actions = chunk
+                    .iter()
+                    .map(|&card_serial| PlayerAction::Stake { card_serial })
+                    .collect();
+                let ix = b.execute_actions(&owner, player.referrer(), actions);
+                push(report, rpc.send(wallet, "stake_cards", &[ix]).await)?;
+            }
+        }
+        Operation::OptimizeLoadout => {
+            require_player(rpc, &owner).await?;
+            push(
+                report,
+                rpc.send_with_compute_limit(
+                    wallet,
+                    "optimize_loadout",
+                    &[b.optimize_loadout(&owner)],
+                    OPTIMIZE_LOADOUT_COMPUTE_UNITS,
+                )
+                .await,
+            )?;
+        }
+        Operation::Unstake => {
+            let player = require_player(rpc, &owner).await?;
+            let serials: Vec<u64> = owned_cards(&player)
+                .filter(|&(index, _)| player.is_card_staked(index))
+                .map(|(_, card)| card.serial)
+                .collect();
+            for chunk in serials.chunks(MAX_ACTIONS_PER_BATCH) {
+                let actions = chunk
+                    .iter()
+                    .map(|&card_serial| PlayerAction::Unstake { card_serial })
+                    .collect();
+                let ix = b.execute_actions(&owner, player.referrer(), actions);
+                push(report, rpc.send(wallet, "unstake_cards", &[ix]).await)?;
+            }
+        }
+        Operation::OpenBooster { count, auto_stake } => {
+            for _ in 0..*count {
+                let player = require_player(rpc, &owner).await?;
+                if player.pending_action() == PendingRandomAction::None {
+                    let commit = b.open_booster_commit(&owner, player.referrer(), *auto_stake);
+                    push(
+                        report,
+                        rpc.send(wallet, "open_booster_commit", &[commit]).await,
+                    )?;
+                }
+                settle_when_revealed(rpc, wallet, report, "settle_open_booster").await?;
+            }
+        }
+        Operation::Recycle { max_rarity } => {
+            let player = require_player(rpc, &owner).await?;
+            let serials: Vec<u64> = owned_cards(&player)
+                .filter(|&(index, card)| {
+                    card.rarity <= *max_rarity
+                        && !player.is_card_staked(index)
+                        && !player.is_card_locked(index)
+                        && !player.is_card_being_recycled(index)
+                })
+                .map(|(_, card)| card.serial)
+                .collect();
+            // Serials stay valid across settlements, so each chunk can be
+            // committed once the previous one has settled
+            for chunk in serials.chunks(MAX_ACTIONS_PER_BATCH) {
+                push(
+                    report,
+                    rpc.send(
+                        wallet,
+                        "recycle_cards_commit",
+                        &[b.recycle_cards_commit(&owner, chunk.to_vec())],
+                    )
+                    .await,
+                )?;
+                settle_when_revealed(rpc, wallet, report, "recycle_cards_settle").await?;
+            }
+        }
+        Operation::Discard { max_rarity } => {
+            let player = require_player(rpc, &owner).await?;
+            let serials: Vec<u64> = owned_cards(&player)
+                .filter(|&(index, card)| {
+                    card.rarity <= *max_rarity
+                        && !player.is_card_staked(index)
+                        && !player.is_card_locked(index)
+                        && !player.is_card_being_recycled(index)
+                })
+                .map(|(_, card)| card.serial)
+                .collect();
+            for chunk in serials.chunks(MAX_ACTIONS_PER_BATCH) {
+                push(
+                    report,
+                    rpc.send(
+                        wallet,
+                        "discard_cards",
+                        &[b.discard_cards(&owner, chunk.to_vec())],
+                    )
+                    .await,
+                )?;
+            }
+        }
+        Operation::Craft { target } => {
+            let player = require_player(rpc, &owner).await?;
+            if player.pending_action() == PendingRandomAction::None {
+                push(
+                    report,
+                    rpc.send(wallet, "craft_commit", &[b.craft_commit(&owner, *target)])
+                        .await,
+                )?;
+            }
+            settle_when_revealed(rpc, wallet, report, "craft_settle").await?;
+        }
+        Operation::Claim => {
+            require_player(rpc, &owner).await?;
+            push(
+                report,
+                rpc.send(wallet, "claim_rewards", &[b.claim_rewards(&owner)])
+                    .await,
+            )?;
+        }
+        Operation::ClaimReferralRewards => {
+            if rpc.referral_balance(&owner).await? == 0 {
+                return Ok(());
+            }
+            let ixs = [
+                create_associated_token_account_idempotent(
+                    &owner,
+                    &owner,
+                    &b.token_mint,
+                    &spl_token::ID,
+                ),
+                b.claim_referral_rewards(&owner),
+            ];
+            push(
+                report,
+                rpc.send(wallet, "claim_referral_rewards", &ixs).await,
+            )?;
+        }
+        Operation::Sweep { recipient } => {
+            let amount = rpc.token_balance(&owner).await?;
+            if amount == 0 {
+                return Ok(());
+            }
+            let ixs = [
+                create_associated_token_account_idempotent(
+                    &owner,
+                    recipient,
+                    &b.token_mint,
+                    &spl_token::ID,
+                ),
+                spl_token::instruction::transfer(
+                    &spl_token::ID,
+                    &b.token_account(&owner),
+                    &b.token_account(recipient),
+                    &owner,
+                    &[],
+                    amount,
+                )?,
+            ];
+            let mut step = rpc.send(wallet, "sweep_tokens", &ixs).await;
+            step.detail = Some(serde_json::json!({ "amount": amount }));
+            push(report, step)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Waits until the committed action's reveal slot has a slot hash, then settles it.
+async fn settle_when_revealed(
+    rpc: &Rpc,
+    wallet: &Keypair,
+    report: &mut WalletReport,
+    step: &str,
+) -> Result<()> {
+    let owner = wallet.pubkey();
+    let player = require_player(rpc, &owner).await?;
+    rpc.wait_for_slot(player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS)
+        .await?;
+
+    let settle = match player.pending_action() {
+        PendingRandomAction::Booster => rpc.builder.settle_open_booster(&owner),
+        PendingRandomAction::Recycle { .. } => rpc.builder.recycle_cards_settle(&owner),
+        PendingRandomAction::Fusion { .. } => rpc.builder.fuse_cards_settle(&owner),
+        PendingRandomAction::Craft { .. } => rpc.builder.craft_card_settle(&owner),
+        PendingRandomAction::None => return Ok(()),
+    };
+    push(report, rpc.send(wallet, step, &[settle]).await)
+}
+
+async fn require_player(rpc: &Rpc, owner: &Pubkey) -> Result<Player> {
+    match rpc.player(owner).await? {
+        Some(player) => Ok(player),
+        None => bail!("no Player account; run purchase-initial-farm first"),
+    }
+}
+
+fn owned_cards(player: &Player) -> impl Iterator<Item = (u8, &ponzimon_client::Card)> {
+    player.cards[..player.card_count as usize]
+        .iter()
+        .enumerate()
+        .map(|(index, card)| (index as u8, card))
+}
+
+/// Greedy pick by hashpower per berry, respecting free slots and berry capacity.
+fn pick_cards_to_stake(player: &Player) -> Vec<u64> {
+    let mut candidates: Vec<_> = owned_cards(player)
+        .filter(|&(index, _)| {
+            !player.is_card_staked(index) && !player.is_card_being_recycled(index)
+        })
+        .map(|(_, card)| card)
+        .collect();
+    candidates.sort_by(|a, b| {
+        // a.hp / a.berry > b.hp / b.berry, compared without division
+        (b.hashpower as u64 * a.berry_consumption.max(1) as u64)
+            .cmp(&(a.hashpower as u64 * b.berry_consumption.max(1) as u64))
+    });
+
+    let mut free_slots = player
+        .farm
+        .total_cards
+        .saturating_sub(player.count_staked_cards());
+    let mut berries = player.berries;
+    let mut picked = Vec::new();
+    for card in candidates {
+        if free_slots == 0 {
+            break;
+        }
+        if berries + card.berry_consumption as u64 <= player.farm.berry_capacity {
+            berries += card.berry_consumption as u64;
+            free_slots -= 1;
+            picked.push(card.serial);
+        }
+    }
+    picked
+}
+
+/// Records a step and turns a failed step into an error so the wallet stops there.
+fn push(report: &mut WalletReport, step: StepReport) -> Result<()> {
+    let error = step.error.clone();
+    let name = step.step.clone();
+    report.steps.push(step);
+    match error {
+        Some(e) => bail!("{name} failed: {e}"),
+        None => Ok(()),
+    }
+}
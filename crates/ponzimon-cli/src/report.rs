@@ -0,0 +1,47 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct WalletReport {
+    pub wallet: String,
+    pub ok: bool,
+    pub steps: Vec<StepReport>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StepReport {
+    pub step: String,
+    pub attempts: u32,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<serde_json::Value>,
+}
+
+impl WalletReport {
+    pub fn new(wallet: String) -> Self {
+        Self {
+            wallet,
+            ok: true,
+            steps: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub fn fail(&mut self, error: &anyhow::Error) {
+        self.ok = false;
+        self.error = Some(format!("{error:#}"));
+    }
+}
+
+pub fn write_per_wallet(dir: &Path, reports: &[WalletReport]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for report in reports {
+        let path = dir.join(format!("{}.json", report.wallet));
+        std::fs::write(path, serde_json::to_vec_pretty(report)?)?;
+    }
+    Ok(())
+}
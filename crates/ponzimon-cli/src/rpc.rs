@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::TokenAccount;
+use anyhow::{Context, Result};
+use ponzimon_client::accounts::{decode_global_state, decode_player};
+use ponzimon_client::{InstructionBuilder, Player};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::report::StepReport;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SendConfig {
+    pub retries: u32,
+    pub retry_delay: Duration,
+    pub compute_unit_limit: u32,
+    pub compute_unit_price: u64,
+}
+
+pub struct Rpc {
+    pub client: RpcClient,
+    pub builder: InstructionBuilder,
+    config: SendConfig,
+}
+
+impl Rpc {
+    /// Connects and reads the fees wallet from GlobalState, and whether the
+    /// buyback is initialized, so builders match the deployment.
+    pub async fn connect(
+        url: &str,
+        program_id: Pubkey,
+        token_mint: Pubkey,
+        config: SendConfig,
+    ) -> Result<Self> {
+        let client = RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed());
+        let mut builder = InstructionBuilder::new(program_id, token_mint, Pubkey::default());
+        let data = client
+            .get_account_data(&builder.global_state())
+            .await
+            .context("fetching GlobalState")?;
+        builder.fees_wallet = decode_global_state(&data)?.fees_wallet;
+        builder.buyback_initialized = client
+            .get_account_with_commitment(&builder.buyback_config(), client.commitment())
+            .await
+            .context("fetching BuybackConfig")?
+            .value
+            .is_some();
+
+        Ok(Self {
+            client,
+            builder,
+            config,
+        })
+    }
+
+    /// The wallet's Player account, or None if it hasn't bought a farm yet.
+    pub async fn player(&self, wallet: &Pubkey) -> Result<Option<Player>> {
+        let account = self
+            .client
+            .get_account_with_commitment(&self.builder.player(wallet), self.client.commitment())
+            .await?
+            .value;
+        account
+            .map(|account| decode_player(&account.data).map_err(Into::into))
+            .transpose()
+    }
+
+    pub async fn token_balance(&self, owner: &Pubkey) -> Result<u64> {
+        self.account_balance(&self.builder.token_account(owner))
+            .await
+    }
+
+    /// Commissions escrowed for `referrer` and not yet claimed.
+    pub async fn referral_balance(&self, referrer: &Pubkey) -> Result<u64> {
+        self.account_balance(&self.builder.referral_vault(referrer))
+            .await
+    }
+
+    async fn account_balance(&self, account: &Pubkey) -> Result<u64> {
+        let account = self
+            .client
+            .get_account_with_commitment(account, self.client.commitment())
+            .await?
+            .value;
+        // A missing token account simply holds nothing; any RPC error is real
+        let Some(account) = account else {
+            return Ok(0);
+        };
+        let token_account = TokenAccount::try_deserialize(&mut &account.data[..])
+            .context("decoding token account")?;
+        Ok(token_account.amount)
+    }
+
+    /// Polls until the cluster has passed `slot`.
+    pub async fn wait_for_slot(&self, slot: u64) -> Result<()> {
+        while self.client.get_slot().await? <= slot {
+            tokio::time::sleep(Duration::from_millis(400)).await;
+        }
+        Ok(())
+    }
+
+    /// Sends `instructions` in one transaction signed by `signer`, retrying with a
+    /// fresh blockhash. Never returns an error: the outcome is in the report.
+    pub async fn send(
+        &self,
+        signer: &Keypair,
+        step: &str,
+        instructions: &[Instruction],
+    ) -> StepReport {
+        self.send_with_compute_limit(signer, step, instructions, self.config.compute_unit_limit)
+            .await
+    }
+
+    /// Like `send`, but requests at least `compute_unit_limit` compute units,
+    /// for instructions that need more than the configured default.
+    pub async fn send_with_compute_limit(
+        &self,
+        signer: &Keypair,
+        step: &str,
+        instructions: &[Instruction],
+        compute_unit_limit: u32,
+    ) -> StepReport {
+        let compute_unit_limit = compute_unit_limit.max(self.config.compute_unit_limit);
+        let mut all = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit),
+            ComputeBudgetInstruction::set_compute_unit_price(self.config.compute_unit_price),
+        ];
+        all.extend_from_slice(instructions);
+
+        let mut last_error = None;
+        for attempt in 1..=self.config.retries {
+            match self.send_once(signer, &all).await {
+                Ok(signature) => {
+                    return StepReport {
+                        step: step.to_string(),
+                        attempts: attempt,
+                        signature: Some(signature),
+                        error: None,
+                        detail: None,
+                    }
+                }
+                Err(e) => {
+                    last_error = Some(format!("{e:#}"));
+                    if attempt < self.config.retries {
+                        tokio::time::sleep(self.config.retry_delay).await;
+                    }
+                }
+            }
+        }
+
+        StepReport {
+            step: step.to_string(),
+            attempts: self.config.retries,
+            signature: None,
+            error: last_error,
+            detail: None,
+        }
+    }
+
+    async fn send_once(&self, signer: &Keypair, instructions: &[Instruction]) -> Result<String> {
+        let blockhash = self.client.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&signer.pubkey()),
+            &[signer],
+            blockhash,
+        );
+        let signature = self
+            .client
+            .send_and_confirm_transaction(&transaction)
+            .await?;
+        Ok(signature.to_string())
+    }
+}
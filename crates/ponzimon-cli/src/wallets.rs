@@ -0,0 +1,22 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use solana_sdk::signature::{read_keypair_file, Keypair};
+
+/// Loads every `*.json` Solana CLI keypair in `dir`, sorted by file name so
+/// runs are reproducible.
+pub fn load_keypair_dir(dir: &Path) -> Result<Vec<Keypair>> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            read_keypair_file(path).map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))
+        })
+        .collect()
+}
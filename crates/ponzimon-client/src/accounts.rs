@@ -0,0 +1,141 @@
+//! Account decoders.
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+
+use ponzimon::constants::MAX_CARDS_PER_PLAYER;
+use ponzimon::instructions::{
+    BuybackConfig, CardCatalog, CardPage, FarmConfig, LegacyCard, LegacyPlayerHead,
+    LegacyPlayerTail,
+};
+use ponzimon::state::{GlobalState, Player};
+
+#[derive(Debug, thiserror::Error)]
+pub enum DecodeError {
+    #[error("account data is {actual} bytes, expected {expected}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("account discriminator does not match")]
+    InvalidDiscriminator,
+    #[error("failed to deserialize: {0}")]
+    Deserialize(String),
+}
+
+/// Decodes a zero-copy Player account.
+pub fn decode_player(data: &[u8]) -> Result<Player, DecodeError> {
+    decode_zero_copy_account(data)
+}
+
+/// A Player account still in the Borsh layout, read in the same three parts
+/// `migrate_player_to_zero_copy` reads it in.
+pub struct LegacyPlayer {
+    pub head: LegacyPlayerHead,
+    pub cards: Vec<LegacyCard>,
+    pub tail: LegacyPlayerTail,
+}
+
+/// Decodes a Player account still in the Borsh layout, i.e. one that has not
+/// gone through `migrate_player_to_zero_copy` yet.
+pub fn decode_legacy_player(data: &[u8]) -> Result<LegacyPlayer, DecodeError> {
+    if data.len() < 8 || data[..8] != Player::DISCRIMINATOR[..] {
+        return Err(DecodeError::InvalidDiscriminator);
+    }
+    let reader = &mut &data[8..];
+    let deserialize_error = |e: std::io::Error| DecodeError::Deserialize(e.to_string());
+    let head = LegacyPlayerHead::deserialize(reader).map_err(deserialize_error)?;
+    let cards = (0..MAX_CARDS_PER_PLAYER)
+        .map(|_| LegacyCard::deserialize(reader))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(deserialize_error)?;
+    let tail = LegacyPlayerTail::deserialize(reader).map_err(deserialize_error)?;
+    Ok(LegacyPlayer { head, cards, tail })
+}
+
+/// True if the Player account still needs `migrate_player_to_zero_copy`.
+pub fn is_legacy_player(data: &[u8]) -> bool {
+    data.len() != 8 + std::mem::size_of::<Player>()
+}
+
+pub fn decode_global_state(data: &[u8]) -> Result<GlobalState, DecodeError> {
+    decode_anchor_account(data)
+}
+
+/// Decodes the zero-copy CardCatalog account.
+pub fn decode_card_catalog(data: &[u8]) -> Result<CardCatalog, DecodeError> {
+    decode_zero_copy_account(data)
+}
+
+pub fn decode_card_page(data: &[u8]) -> Result<CardPage, DecodeError> {
+    decode_anchor_account(data)
+}
+
+pub fn decode_farm_config(data: &[u8]) -> Result<FarmConfig, DecodeError> {
+    decode_anchor_account(data)
+}
+
+pub fn decode_buyback_config(data: &[u8]) -> Result<BuybackConfig, DecodeError> {
+    decode_anchor_account(data)
+}
+
+fn decode_zero_copy_account<T: bytemuck::Pod + Discriminator>(
+    data: &[u8],
+) -> Result<T, DecodeError> {
+    let expected = 8 + std::mem::size_of::<T>();
+    if data.len() != expected {
+        return Err(DecodeError::InvalidLength {
+            expected,
+            actual: data.len(),
+        });
+    }
+    if data[..8] != T::DISCRIMINATOR[..] {
+        return Err(DecodeError::InvalidDiscriminator);
+    }
+    // Account data carries no alignment guarantee, so read unaligned
+    bytemuck::try_pod_read_unaligned::<T>(&data[8..])
+        .map_err(|e| DecodeError::Deserialize(e.to_string()))
+}
+
+fn decode_anchor_account<T: AccountDeserialize + Discriminator>(
+    data: &[u8],
+) -> Result<T, DecodeError> {
+    if data.len() < 8 || data[..8] != T::DISCRIMINATOR[..] {
+        return Err(DecodeError::InvalidDiscriminator);
+    }
+    T::try_deserialize(&mut &data[..]).map_err(|e| DecodeError::Deserialize(e.to_string()))
+}
+
+/// Decodes the return data of a simulated view instruction
+/// (`get_pending_rewards`, `get_player_summary`, `get_global_stats`).
+pub fn decode_return_data<T: AnchorDeserialize>(data: &[u8]) -> Result<T, DecodeError> {
+    T::try_from_slice(data).map_err(|e| DecodeError::Deserialize(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLANA_TOOLS_JS: &str = include_str!("../../../src/utils/solanaTools.js");
+
+    fn js_const(name: &str) -> usize {
+        let prefix = format!("const {name} = ");
+        let line = SOLANA_TOOLS_JS
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(prefix.as_str()))
+            .unwrap_or_else(|| panic!("{name} not found in solanaTools.js"));
+        line.trim_end_matches(';').trim().parse().unwrap()
+    }
+
+    /// The frontend decodes Player by hand (decodePlayerData in
+    /// src/utils/solanaTools.js); keep its constants in step with state.rs.
+    #[test]
+    fn frontend_player_layout_matches() {
+        assert_eq!(
+            std::mem::size_of::<Player>(),
+            js_const("PLAYER_ACCOUNT_SIZE"),
+            "PLAYER_ACCOUNT_SIZE"
+        );
+        assert_eq!(
+            std::mem::offset_of!(Player, accrued_rewards),
+            js_const("PLAYER_ACCRUED_REWARDS_OFFSET"),
+            "PLAYER_ACCRUED_REWARDS_OFFSET"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Event decoders for `emit!` logs ("Program data: <base64>").
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::Engine;
+
+use ponzimon::instructions::*;
+
+macro_rules! ponzimon_events {
+    ($($event:ident),* $(,)?) => {
+        /// Every event the program can emit.
+        pub enum PonzimonEvent {
+            $($event($event),)*
+        }
+
+        /// Decodes one event from its raw bytes (discriminator + Borsh body).
+        /// Returns None for data that isn't a known ponzimon event.
+        pub fn decode_event(data: &[u8]) -> Option<PonzimonEvent> {
+            if data.len() < 8 {
+                return None;
+            }
+            let (discriminator, body) = data.split_at(8);
+            $(
+                if discriminator == &$event::DISCRIMINATOR[..] {
+                    return $event::try_from_slice(body).ok().map(PonzimonEvent::$event);
+                }
+            )*
+            None
+        }
+    };
+}
+
+ponzimon_events!(
+    FarmUpgraded,
+    CardStaked,
+    CardUnstaked,
+    CardDiscarded,
+    CardsDiscarded,
+    CardsLockChanged,
+    BoosterOpened,
+    CardsRecycled,
+    InitialFarmPurchased,
+    CatalogUpdated,
+    CardsMovedToPage,
+    CardsMovedFromPage,
+    CardSerialsMigrated,
+    PlayerMigratedToZeroCopy,
+    CardsFused,
+    ShardsSpent,
+    CardCrafted,
+    BuybackUpdated,
+    CardSoldToProtocol,
+    FarmConfigUpdated,
+    ActionsExecuted,
+    LoadoutOptimized,
+    ReferralRewardsClaimed,
+);
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Extracts every ponzimon event from a transaction's log messages.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<PonzimonEvent> {
+    logs.iter()
+        .filter_map(|line| line.as_ref().strip_prefix(PROGRAM_DATA_PREFIX))
+        .filter_map(|encoded| {
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok()
+        })
+        .filter_map(|data| decode_event(&data))
+        .collect()
+}
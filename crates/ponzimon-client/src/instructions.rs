@@ -0,0 +1,782 @@
+//! Typed instruction builders, one per program instruction.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::{instruction::Instruction, system_program, sysvar};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token;
+
+use ponzimon::instructions::{CatalogEntry, CraftTarget, FarmTier, PlayerAction, RARITY_COUNT};
+use ponzimon::{accounts, instruction};
+
+use crate::pda;
+
+/// Builds instructions for one deployment (program id + token mint).
+#[derive(Debug, Clone, Copy)]
+pub struct InstructionBuilder {
+    pub program_id: Pubkey,
+    pub token_mint: Pubkey,
+    /// Protocol fee recipient, as stored in GlobalState.fees_wallet
+    pub fees_wallet: Pubkey,
+    /// Whether initialize_buyback has run. Until then open_booster_commit
+    /// omits the buyback accounts and the program applies a 0% share.
+    pub buyback_initialized: bool,
+}
+
+impl InstructionBuilder {
+    pub fn new(program_id: Pubkey, token_mint: Pubkey, fees_wallet: Pubkey) -> Self {
+        Self {
+            program_id,
+            token_mint,
+            fees_wallet,
+            buyback_initialized: true,
+        }
+    }
+
+    fn build(&self, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn global_state(&self) -> Pubkey {
+        pda::global_state(&self.program_id, &self.token_mint).0
+    }
+
+    pub fn player(&self, player_wallet: &Pubkey) -> Pubkey {
+        pda::player(&self.program_id, player_wallet, &self.token_mint).0
+    }
+
+    pub fn rewards_vault(&self) -> Pubkey {
+        pda::rewards_vault(&self.program_id, &self.token_mint).0
+    }
+
+    pub fn card_catalog(&self) -> Pubkey {
+        pda::card_catalog(&self.program_id, &self.token_mint).0
+    }
+
+    pub fn farm_config(&self) -> Pubkey {
+        pda::farm_config(&self.program_id, &self.token_mint).0
+    }
+
+    pub fn buyback_config(&self) -> Pubkey {
+        pda::buyback_config(&self.program_id, &self.token_mint).0
+    }
+
+    pub fn buyback_vault(&self) -> Pubkey {
+        pda::buyback_vault(&self.program_id, &self.token_mint).0
+    }
+
+    /// Escrow token account holding `referrer_wallet`'s referral commissions.
+    pub fn referral_vault(&self, referrer_wallet: &Pubkey) -> Pubkey {
+        pda::referral_vault(&self.program_id, &self.token_mint, referrer_wallet).0
+    }
+
+    pub fn card_page(&self, player_wallet: &Pubkey, page: u8) -> Pubkey {
+        pda::card_page(&self.program_id, &self.player(player_wallet), page).0
+    }
+
+    /// Associated token account of `owner` for the game token.
+    pub fn token_account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address(owner, &self.token_mint)
+    }
+
+    // ── Player instructions ────────────────────────────────────────────────
+
+    pub fn purchase_initial_farm(
+        &self,
+        player_wallet: &Pubkey,
+        referrer_wallet: Option<Pubkey>,
+    ) -> Instruction {
+        self.build(
+            accounts::PurchaseInitialFarm {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                fees_wallet: self.fees_wallet,
+                referrer_wallet,
+                card_catalog: self.card_catalog(),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+                player_token_account: self.token_account(player_wallet),
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::PurchaseInitialFarm {},
+        )
+    }
+
+    pub fn discard_card(&self, player_wallet: &Pubkey, card_serial: u64) -> Instruction {
+        self.build(
+            accounts::DiscardCard {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                fees_token_account: self.token_account(&self.fees_wallet),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+            },
+            instruction::DiscardCard { card_serial },
+        )
+    }
+
+    pub fn discard_cards(&self, player_wallet: &Pubkey, card_serials: Vec<u64>) -> Instruction {
+        self.build(
+            accounts::DiscardCards {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+            },
+            instruction::DiscardCards { card_serials },
+        )
+    }
+
+    pub fn lock_cards(&self, player_wallet: &Pubkey, card_serials: Vec<u64>) -> Instruction {
+        self.build(
+            accounts::LockCards {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                token_mint: self.token_mint,
+            },
+            instruction::LockCards { card_serials },
+        )
+    }
+
+    pub fn unlock_cards(&self, player_wallet: &Pubkey, card_serials: Vec<u64>) -> Instruction {
+        self.build(
+            accounts::LockCards {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                token_mint: self.token_mint,
+            },
+            instruction::UnlockCards { card_serials },
+        )
+    }
+
+    pub fn stake_card(&self, player_wallet: &Pubkey, card_serial: u64) -> Instruction {
+        self.build(
+            accounts::StakeCard {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                token_mint: self.token_mint,
+                player_token_account: self.token_account(player_wallet),
+                token_program: token::ID,
+            },
+            instruction::StakeCard { card_serial },
+        )
+    }
+
+    pub fn unstake_card(&self, player_wallet: &Pubkey, card_serial: u64) -> Instruction {
+        self.build(
+            accounts::UnstakeCard {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                token_mint: self.token_mint,
+                player_token_account: self.token_account(player_wallet),
+                token_program: token::ID,
+            },
+            instruction::UnstakeCard { card_serial },
+        )
+    }
+
+    /// `referrer_wallet` must be the player's recorded referrer, if any.
+    pub fn upgrade_farm(
+        &self,
+        player_wallet: &Pubkey,
+        referrer_wallet: Option<Pubkey>,
+        farm_type: u8,
+    ) -> Instruction {
+        self.build(
+            accounts::UpgradeFarm {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                fees_token_account: self.token_account(&self.fees_wallet),
+                referrer_wallet,
+                referral_vault: referrer_wallet.map(|r| self.referral_vault(&r)),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::UpgradeFarm { farm_type },
+        )
+    }
+
+    /// `referrer_wallet` must be the player's recorded referrer, if any.
+    pub fn upgrade_farm_to(
+        &self,
+        player_wallet: &Pubkey,
+        referrer_wallet: Option<Pubkey>,
+        target_farm_type: u8,
+    ) -> Instruction {
+        self.build(
+            accounts::UpgradeFarm {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                fees_token_account: self.token_account(&self.fees_wallet),
+                referrer_wallet,
+                referral_vault: referrer_wallet.map(|r| self.referral_vault(&r)),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::UpgradeFarmTo { target_farm_type },
+        )
+    }
+
+    pub fn claim_rewards(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::ClaimRewards {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+            },
+            instruction::ClaimRewards {},
+        )
+    }
+
+    /// Withdraws the commissions escrowed for `referrer_wallet` to its token account.
+    pub fn claim_referral_rewards(&self, referrer_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::ClaimReferralRewards {
+                referrer_wallet: *referrer_wallet,
+                global_state: self.global_state(),
+                referral_vault: self.referral_vault(referrer_wallet),
+                referrer_token_account: self.token_account(referrer_wallet),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+            },
+            instruction::ClaimReferralRewards {},
+        )
+    }
+
+    /// `referrer_wallet` must be the player's recorded referrer, if any; it is
+    /// only used by `PlayerAction::UpgradeFarm`.
+    pub fn execute_actions(
+        &self,
+        player_wallet: &Pubkey,
+        referrer_wallet: Option<Pubkey>,
+        actions: Vec<PlayerAction>,
+    ) -> Instruction {
+        self.build(
+            accounts::ExecuteActions {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                fees_token_account: self.token_account(&self.fees_wallet),
+                referrer_wallet,
+                referral_vault: referrer_wallet.map(|r| self.referral_vault(&r)),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::ExecuteActions { actions },
+        )
+    }
+
+    pub fn optimize_loadout(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::OptimizeLoadout {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                token_mint: self.token_mint,
+                player_token_account: self.token_account(player_wallet),
+                token_program: token::ID,
+            },
+            instruction::OptimizeLoadout {},
+        )
+    }
+
+    /// `referrer_wallet` must be the player's recorded referrer, if any.
+    pub fn open_booster_commit(
+        &self,
+        player_wallet: &Pubkey,
+        referrer_wallet: Option<Pubkey>,
+        auto_stake: bool,
+    ) -> Instruction {
+        self.build(
+            accounts::OpenBoosterCommit {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                player_token_account: self.token_account(player_wallet),
+                fees_token_account: self.token_account(&self.fees_wallet),
+                referrer_wallet,
+                referral_vault: referrer_wallet.map(|r| self.referral_vault(&r)),
+                buyback_config: self.buyback_initialized.then(|| self.buyback_config()),
+                buyback_vault: self.buyback_initialized.then(|| self.buyback_vault()),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::OpenBoosterCommit { auto_stake },
+        )
+    }
+
+    pub fn settle_open_booster(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::SettleOpenBooster {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                card_catalog: self.card_catalog(),
+                token_mint: self.token_mint,
+                slot_hashes: sysvar::slot_hashes::ID,
+            },
+            instruction::SettleOpenBooster {},
+        )
+    }
+
+    pub fn recycle_cards_commit(
+        &self,
+        player_wallet: &Pubkey,
+        card_serials: Vec<u64>,
+    ) -> Instruction {
+        self.build(
+            accounts::RecycleCardsCommit {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                token_mint: self.token_mint,
+            },
+            instruction::RecycleCardsCommit { card_serials },
+        )
+    }
+
+    pub fn recycle_cards_settle(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::RecycleCardsSettle {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                card_catalog: self.card_catalog(),
+                token_mint: self.token_mint,
+                slot_hashes: sysvar::slot_hashes::ID,
+            },
+            instruction::RecycleCardsSettle {},
+        )
+    }
+
+    pub fn fuse_cards_commit(&self, player_wallet: &Pubkey, card_serials: Vec<u64>) -> Instruction {
+        self.build(
+            accounts::FuseCardsCommit {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                token_mint: self.token_mint,
+            },
+            instruction::FuseCardsCommit { card_serials },
+        )
+    }
+
+    pub fn fuse_cards_settle(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::FuseCardsSettle {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                card_catalog: self.card_catalog(),
+                token_mint: self.token_mint,
+                slot_hashes: sysvar::slot_hashes::ID,
+            },
+            instruction::FuseCardsSettle {},
+        )
+    }
+
+    pub fn craft_commit(&self, player_wallet: &Pubkey, target: CraftTarget) -> Instruction {
+        self.build(
+            accounts::CraftCommit {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                card_catalog: self.card_catalog(),
+                token_mint: self.token_mint,
+            },
+            instruction::CraftCommit { target },
+        )
+    }
+
+    pub fn craft_card_settle(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::CraftCardSettle {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                card_catalog: self.card_catalog(),
+                token_mint: self.token_mint,
+                slot_hashes: sysvar::slot_hashes::ID,
+            },
+            instruction::CraftCardSettle {},
+        )
+    }
+
+    pub fn cancel_pending_action(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::CancelPendingAction {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                token_mint: self.token_mint,
+            },
+            instruction::CancelPendingAction {},
+        )
+    }
+
+    pub fn initialize_card_page(&self, player_wallet: &Pubkey, page: u8) -> Instruction {
+        self.build(
+            accounts::InitializeCardPage {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                card_page: self.card_page(player_wallet, page),
+                token_mint: self.token_mint,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeCardPage { page },
+        )
+    }
+
+    fn move_cards_page_accounts(
+        &self,
+        player_wallet: &Pubkey,
+        page: u8,
+    ) -> accounts::MoveCardsPage {
+        accounts::MoveCardsPage {
+            player_wallet: *player_wallet,
+            player: self.player(player_wallet),
+            card_page: self.card_page(player_wallet, page),
+            token_mint: self.token_mint,
+        }
+    }
+
+    pub fn move_cards_to_page(
+        &self,
+        player_wallet: &Pubkey,
+        page: u8,
+        card_serials: Vec<u64>,
+    ) -> Instruction {
+        self.build(
+            self.move_cards_page_accounts(player_wallet, page),
+            instruction::MoveCardsToPage { page, card_serials },
+        )
+    }
+
+    pub fn move_cards_from_page(
+        &self,
+        player_wallet: &Pubkey,
+        page: u8,
+        card_serials: Vec<u64>,
+    ) -> Instruction {
+        self.build(
+            self.move_cards_page_accounts(player_wallet, page),
+            instruction::MoveCardsFromPage { page, card_serials },
+        )
+    }
+
+    pub fn migrate_card_serials(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::MigrateCardSerials {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                token_mint: self.token_mint,
+                system_program: system_program::ID,
+            },
+            instruction::MigrateCardSerials {},
+        )
+    }
+
+    pub fn migrate_player_to_zero_copy(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::MigratePlayerToZeroCopy {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                token_mint: self.token_mint,
+                system_program: system_program::ID,
+            },
+            instruction::MigratePlayerToZeroCopy {},
+        )
+    }
+
+    // ── Views (simulate, then decode with accounts::decode_return_data) ────
+
+    pub fn get_pending_rewards(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::PlayerView {
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                token_mint: self.token_mint,
+            },
+            instruction::GetPendingRewards {
+                owner: *player_wallet,
+            },
+        )
+    }
+
+    pub fn get_player_summary(&self, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::PlayerView {
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                token_mint: self.token_mint,
+            },
+            instruction::GetPlayerSummary {
+                owner: *player_wallet,
+            },
+        )
+    }
+
+    pub fn get_global_stats(&self) -> Instruction {
+        self.build(
+            accounts::GlobalView {
+                global_state: self.global_state(),
+                token_mint: self.token_mint,
+            },
+            instruction::GetGlobalStats {},
+        )
+    }
+
+    // ── Authority instructions ─────────────────────────────────────────────
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_program(
+        &self,
+        authority: &Pubkey,
+        start_slot: u64,
+        total_supply: u64,
+        reward_rate: u64,
+        initial_farm_purchase_fee_lamports: Option<u64>,
+        booster_pack_cost_microtokens: Option<u64>,
+        gamble_fee_lamports: Option<u64>,
+        staking_lockup_slots: u64,
+        token_reward_rate: u64,
+    ) -> Instruction {
+        self.build(
+            accounts::InitializeProgram {
+                authority: *authority,
+                global_state: self.global_state(),
+                fees_wallet: self.fees_wallet,
+                fees_token_account: self.token_account(&self.fees_wallet),
+                rewards_vault: self.rewards_vault(),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+                associated_token_program: associated_token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::InitializeProgram {
+                start_slot,
+                total_supply,
+                reward_rate,
+                initial_farm_purchase_fee_lamports,
+                booster_pack_cost_microtokens,
+                gamble_fee_lamports,
+                staking_lockup_slots,
+                token_reward_rate,
+            },
+        )
+    }
+
+    pub fn toggle_production(&self, authority: &Pubkey, enable: bool) -> Instruction {
+        self.build(
+            accounts::ToggleProduction {
+                authority: *authority,
+                global_state: self.global_state(),
+            },
+            instruction::ToggleProduction { enable },
+        )
+    }
+
+    /// See `ponzimon::instructions::update_parameter` for the index table.
+    pub fn update_parameter(
+        &self,
+        authority: &Pubkey,
+        parameter_index: u8,
+        parameter_value: u64,
+    ) -> Instruction {
+        self.build(
+            accounts::UpdateParameters {
+                authority: *authority,
+                global_state: self.global_state(),
+            },
+            instruction::UpdateParameter {
+                parameter_index,
+                parameter_value,
+            },
+        )
+    }
+
+    pub fn update_pool_manual(&self, authority: &Pubkey) -> Instruction {
+        self.build(
+            accounts::UpdatePool {
+                authority: *authority,
+                global_state: self.global_state(),
+            },
+            instruction::UpdatePoolManual {},
+        )
+    }
+
+    pub fn reset_player(&self, authority: &Pubkey, player_wallet: &Pubkey) -> Instruction {
+        self.build(
+            accounts::ResetPlayer {
+                authority: *authority,
+                global_state: self.global_state(),
+                player: self.player(player_wallet),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+                player_wallet: *player_wallet,
+            },
+            instruction::ResetPlayer {},
+        )
+    }
+
+    pub fn initialize_buyback(&self, authority: &Pubkey) -> Instruction {
+        self.build(
+            accounts::InitializeBuyback {
+                authority: *authority,
+                global_state: self.global_state(),
+                buyback_config: self.buyback_config(),
+                buyback_vault: self.buyback_vault(),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::InitializeBuyback {},
+        )
+    }
+
+    pub fn update_buyback(
+        &self,
+        authority: &Pubkey,
+        prices: [u64; RARITY_COUNT],
+        fee_share: u8,
+    ) -> Instruction {
+        self.build(
+            accounts::UpdateBuyback {
+                authority: *authority,
+                global_state: self.global_state(),
+                buyback_config: self.buyback_config(),
+                token_mint: self.token_mint,
+            },
+            instruction::UpdateBuyback { prices, fee_share },
+        )
+    }
+
+    pub fn sell_card_to_protocol(&self, player_wallet: &Pubkey, card_serial: u64) -> Instruction {
+        self.build(
+            accounts::SellCardToProtocol {
+                player_wallet: *player_wallet,
+                player: self.player(player_wallet),
+                global_state: self.global_state(),
+                rewards_vault: self.rewards_vault(),
+                buyback_config: self.buyback_config(),
+                buyback_vault: self.buyback_vault(),
+                player_token_account: self.token_account(player_wallet),
+                token_mint: self.token_mint,
+                token_program: token::ID,
+            },
+            instruction::SellCardToProtocol { card_serial },
+        )
+    }
+
+    pub fn initialize_farm_config(&self, authority: &Pubkey) -> Instruction {
+        self.build(
+            accounts::InitializeFarmConfig {
+                authority: *authority,
+                global_state: self.global_state(),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeFarmConfig {},
+        )
+    }
+
+    pub fn update_farm_config(&self, authority: &Pubkey, tiers: Vec<FarmTier>) -> Instruction {
+        self.build(
+            accounts::UpdateFarmConfig {
+                authority: *authority,
+                global_state: self.global_state(),
+                farm_config: self.farm_config(),
+                token_mint: self.token_mint,
+            },
+            instruction::UpdateFarmConfig { tiers },
+        )
+    }
+
+    pub fn initialize_card_catalog(&self, authority: &Pubkey) -> Instruction {
+        self.build(
+            accounts::InitializeCardCatalog {
+                authority: *authority,
+                global_state: self.global_state(),
+                card_catalog: self.card_catalog(),
+                token_mint: self.token_mint,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeCardCatalog {},
+        )
+    }
+
+    fn update_card_catalog_accounts(&self, authority: &Pubkey) -> accounts::UpdateCardCatalog {
+        accounts::UpdateCardCatalog {
+            authority: *authority,
+            global_state: self.global_state(),
+            card_catalog: self.card_catalog(),
+            token_mint: self.token_mint,
+        }
+    }
+
+    pub fn add_catalog_cards(&self, authority: &Pubkey, entries: Vec<CatalogEntry>) -> Instruction {
+        self.build(
+            self.update_card_catalog_accounts(authority),
+            instruction::AddCatalogCards { entries },
+        )
+    }
+
+    pub fn set_catalog_card_retired(
+        &self,
+        authority: &Pubkey,
+        card_id: u16,
+        retired: bool,
+    ) -> Instruction {
+        self.build(
+            self.update_card_catalog_accounts(authority),
+            instruction::SetCatalogCardRetired { card_id, retired },
+        )
+    }
+}
@@ -0,0 +1,24 @@
+//! Client-side helpers for the ponzimon program.
+//!
+//! Everything here is built from the program crate's own types: instruction
+//! data and account metas come from Anchor's generated `instruction` and
+//! `accounts` modules, and decoders read `state`/`instructions` types directly.
+//! A layout change in the program therefore breaks compilation here instead of
+//! silently mis-decoding accounts.
+
+pub mod accounts;
+pub mod events;
+pub mod instructions;
+pub mod pda;
+
+pub use accounts::DecodeError;
+pub use instructions::InstructionBuilder;
+
+pub use ponzimon::constants::{CANCEL_TIMEOUT_SLOTS, MIN_RANDOMNESS_DELAY_SLOTS};
+pub use ponzimon::instructions::{
+    BuybackConfig, CardCatalog, CardPage, CatalogEntry, CraftTarget, FarmConfig, FarmTier,
+    GlobalStatsView, PendingRewardsView, PlayerAction, PlayerSummaryView, MAX_ACTIONS_PER_BATCH,
+    OPTIMIZE_LOADOUT_COMPUTE_UNITS, RARITY_COUNT,
+};
+pub use ponzimon::state::{Card, Farm, GlobalState, PendingRandomAction, Player};
+pub use ponzimon::ID as PROGRAM_ID;
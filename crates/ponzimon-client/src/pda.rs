@@ -0,0 +1,53 @@
+//! Program-derived addresses. Every helper takes the program id because devnet
+//! and mainnet deployments live at different addresses.
+
+use anchor_lang::prelude::Pubkey;
+
+pub use ponzimon::constants::{GLOBAL_STATE_SEED, PLAYER_SEED, REWARDS_VAULT_SEED};
+pub use ponzimon::instructions::{
+    BUYBACK_CONFIG_SEED, BUYBACK_VAULT_SEED, CARD_CATALOG_SEED, CARD_PAGE_SEED, FARM_CONFIG_SEED,
+    REFERRAL_VAULT_SEED,
+};
+
+pub fn global_state(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_STATE_SEED, token_mint.as_ref()], program_id)
+}
+
+pub fn player(program_id: &Pubkey, player_wallet: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[PLAYER_SEED, player_wallet.as_ref(), token_mint.as_ref()],
+        program_id,
+    )
+}
+
+pub fn rewards_vault(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARDS_VAULT_SEED, token_mint.as_ref()], program_id)
+}
+
+pub fn card_catalog(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CARD_CATALOG_SEED, token_mint.as_ref()], program_id)
+}
+
+pub fn farm_config(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_CONFIG_SEED, token_mint.as_ref()], program_id)
+}
+
+pub fn buyback_config(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BUYBACK_CONFIG_SEED, token_mint.as_ref()], program_id)
+}
+
+pub fn buyback_vault(program_id: &Pubkey, token_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BUYBACK_VAULT_SEED, token_mint.as_ref()], program_id)
+}
+
+pub fn referral_vault(program_id: &Pubkey, token_mint: &Pubkey, referrer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REFERRAL_VAULT_SEED, token_mint.as_ref(), referrer.as_ref()],
+        program_id,
+    )
+}
+
+/// `player` is the Player PDA, not the wallet.
+pub fn card_page(program_id: &Pubkey, player: &Pubkey, page: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CARD_PAGE_SEED, player.as_ref(), &[page]], program_id)
+}
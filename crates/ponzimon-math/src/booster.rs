@@ -0,0 +1,173 @@
+//! Booster pack rarity table, pity and unbiased index picking.
+//!
+//! Rarities are the ids in [`crate::constants`]: COMMON = 0 up to MEGA_RARE = 6.
+
+/// Probability of each rarity in a booster slot, in per-mille, indexed by rarity.
+pub const BOOSTER_RARITY_PER_MILLE: [u32; 7] = [
+    500, // COMMON       50.0%
+    250, // UNCOMMON     25.0%
+    150, // RARE         15.0%
+    60,  // DOUBLE_RARE   6.0%
+    30,  // VERY_RARE     3.0%
+    9,   // SUPER_RARE    0.9%
+    1,   // MEGA_RARE     0.1%
+];
+
+/// Maps a uniformly random u32 onto `0..len` without modulo bias.
+pub fn pick_index(random_u32: u32, len: usize) -> usize {
+    (random_u32 as u64 * len as u64 / (u32::MAX as u64 + 1)) as usize
+}
+
+/// Rolls one booster slot's rarity from a uniformly random u32.
+pub fn roll_booster_rarity(random_u32: u32) -> u8 {
+    // The maximum possible result is 999, so the full u32 range maps onto
+    // 0..1000 without bias or out-of-bounds results.
+    let mut random_per_mille = pick_index(random_u32, 1000) as u32;
+    for (rarity, &odds) in BOOSTER_RARITY_PER_MILLE.iter().enumerate() {
+        if random_per_mille < odds {
+            return rarity as u8;
+        }
+        random_per_mille -= odds;
+    }
+    (BOOSTER_RARITY_PER_MILLE.len() - 1) as u8
+}
+
+/// Pity: if this pack would be the `pity_threshold`-th in a row without a
+/// card at or above `pity_rarity`, force the last slot up to it. A threshold
+/// of 0 disables pity. Updates the player's streak and reports whether pity
+/// fired.
+pub fn apply_pity(
+    rarities: &mut [u8],
+    boosters_without_pity: &mut u8,
+    pity_rarity: u8,
+    pity_threshold: u8,
+) -> bool {
+    if rarities.iter().any(|&r| r >= pity_rarity) {
+        *boosters_without_pity = 0;
+        return false;
+    }
+    *boosters_without_pity = boosters_without_pity.saturating_add(1);
+    if pity_threshold > 0 && *boosters_without_pity >= pity_threshold {
+        if let Some(last) = rarities.last_mut() {
+            *last = pity_rarity;
+        }
+        *boosters_without_pity = 0;
+        return true;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{COMMON, DOUBLE_RARE, MEGA_RARE, RARE, SUPER_RARE, UNCOMMON, VERY_RARE};
+
+    /// The rarity match settle_open_booster used before the table moved here.
+    fn baseline_rarity(random_u32: u32) -> u8 {
+        let random_percent = (random_u32 as u64 * 1000 / (u32::MAX as u64 + 1)) as u32;
+        match random_percent {
+            0..=499 => COMMON,
+            500..=749 => UNCOMMON,
+            750..=899 => RARE,
+            900..=959 => DOUBLE_RARE,
+            960..=989 => VERY_RARE,
+            990..=998 => SUPER_RARE,
+            _ => MEGA_RARE,
+        }
+    }
+
+    /// Smallest u32 that maps onto `per_mille`.
+    fn first_u32_for(per_mille: u64) -> u32 {
+        ((per_mille << 32).div_ceil(1000)) as u32
+    }
+
+    #[test]
+    fn roll_booster_rarity_boundaries() {
+        let boundaries = [
+            (500, COMMON, UNCOMMON),
+            (750, UNCOMMON, RARE),
+            (900, RARE, DOUBLE_RARE),
+            (960, DOUBLE_RARE, VERY_RARE),
+            (990, VERY_RARE, SUPER_RARE),
+            (999, SUPER_RARE, MEGA_RARE),
+        ];
+        for (per_mille, below, at) in boundaries {
+            let first = first_u32_for(per_mille);
+            assert_eq!(roll_booster_rarity(first - 1), below, "below {per_mille}");
+            assert_eq!(roll_booster_rarity(first), at, "at {per_mille}");
+        }
+        assert_eq!(roll_booster_rarity(0), COMMON);
+        assert_eq!(roll_booster_rarity(u32::MAX), MEGA_RARE);
+    }
+
+    #[test]
+    fn roll_booster_rarity_matches_baseline() {
+        let step = u32::MAX / 100_003;
+        let mut random_u32 = 0u32;
+        while let Some(next) = random_u32.checked_add(step) {
+            assert_eq!(roll_booster_rarity(random_u32), baseline_rarity(random_u32));
+            random_u32 = next;
+        }
+        for per_mille in 1..1000 {
+            let first = first_u32_for(per_mille);
+            for random_u32 in [first - 1, first] {
+                assert_eq!(roll_booster_rarity(random_u32), baseline_rarity(random_u32));
+            }
+        }
+    }
+
+    #[test]
+    fn booster_odds_sum_to_one() {
+        assert_eq!(BOOSTER_RARITY_PER_MILLE.iter().sum::<u32>(), 1000);
+    }
+
+    #[test]
+    fn pick_index_covers_range_without_overflow() {
+        assert_eq!(pick_index(0, 7), 0);
+        assert_eq!(pick_index(u32::MAX, 7), 6);
+        assert_eq!(pick_index(u32::MAX, 1), 0);
+        assert_eq!(pick_index(1 << 31, 2), 1);
+        assert_eq!(pick_index((1 << 31) - 1, 2), 0);
+    }
+
+    #[test]
+    fn apply_pity_resets_streak_on_a_hit() {
+        let mut rarities = [COMMON, COMMON, RARE, COMMON, COMMON];
+        let mut streak = 7;
+        assert!(!apply_pity(&mut rarities, &mut streak, RARE, 10));
+        assert_eq!(streak, 0);
+        assert_eq!(rarities, [COMMON, COMMON, RARE, COMMON, COMMON]);
+
+        // Anything above the pity rarity counts too
+        let mut rarities = [COMMON, MEGA_RARE, COMMON, COMMON, COMMON];
+        let mut streak = 9;
+        assert!(!apply_pity(&mut rarities, &mut streak, RARE, 10));
+        assert_eq!(streak, 0);
+    }
+
+    #[test]
+    fn apply_pity_fires_on_the_threshold_pack() {
+        let mut streak = 8;
+        let mut rarities = [COMMON; 5];
+        assert!(!apply_pity(&mut rarities, &mut streak, RARE, 10));
+        assert_eq!(streak, 9);
+        assert_eq!(rarities, [COMMON; 5]);
+
+        let mut rarities = [COMMON, UNCOMMON, COMMON, COMMON, UNCOMMON];
+        assert!(apply_pity(&mut rarities, &mut streak, RARE, 10));
+        assert_eq!(streak, 0);
+        assert_eq!(rarities, [COMMON, UNCOMMON, COMMON, COMMON, RARE]);
+    }
+
+    #[test]
+    fn apply_pity_threshold_zero_disables_pity() {
+        let mut streak = u8::MAX - 1;
+        for _ in 0..3 {
+            let mut rarities = [COMMON; 5];
+            assert!(!apply_pity(&mut rarities, &mut streak, RARE, 0));
+            assert_eq!(rarities, [COMMON; 5]);
+        }
+        // The streak saturates instead of wrapping
+        assert_eq!(streak, u8::MAX);
+    }
+}
@@ -0,0 +1,17 @@
+//! Game constants shared with the program. The program's constants.rs
+//! re-exports these, so off-chain tools never need the program crate itself.
+
+/// Fixed-point scale of `acc_tokens_per_hashpower`.
+pub const ACC_SCALE: u128 = 1_000_000_000_000;
+
+/// Size of a Player's card array.
+pub const MAX_CARDS_PER_PLAYER: u8 = 128;
+
+/// Card rarities, in upgrade order.
+pub const COMMON: u8 = 0;
+pub const UNCOMMON: u8 = 1;
+pub const RARE: u8 = 2;
+pub const DOUBLE_RARE: u8 = 3;
+pub const VERY_RARE: u8 = 4;
+pub const SUPER_RARE: u8 = 5;
+pub const MEGA_RARE: u8 = 6;
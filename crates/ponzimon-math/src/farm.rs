@@ -0,0 +1,27 @@
+//! Farm capacity rules for staking.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StakeError {
+    /// Every farm slot already holds a staked card
+    NoFreeSlot,
+    /// Staking the card would exceed the farm's berry capacity
+    BerryCapacityExceeded,
+}
+
+/// Whether one more card consuming `card_berry_consumption` can be staked on
+/// a farm with `total_cards` slots and `berry_capacity`, given what is staked.
+pub fn check_stake(
+    staked_cards: u8,
+    total_cards: u8,
+    berries: u64,
+    berry_capacity: u64,
+    card_berry_consumption: u64,
+) -> Result<(), StakeError> {
+    if staked_cards >= total_cards {
+        return Err(StakeError::NoFreeSlot);
+    }
+    if berries.saturating_add(card_berry_consumption) > berry_capacity {
+        return Err(StakeError::BerryCapacityExceeded);
+    }
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Splits of token payments into burn, buyback funding, protocol fee and
+//! referral commission.
+
+/// Splits a payment into `(burn, fees)` by `burn_rate` percent.
+pub fn split_burn(total_amount: u64, burn_rate: u8) -> (u64, u64) {
+    let burn_amount = total_amount
+        .saturating_mul(burn_rate as u64)
+        .saturating_div(100);
+    (burn_amount, total_amount.saturating_sub(burn_amount))
+}
+
+/// Splits the non-burned fees into `(referral_commission, protocol_fee)` by
+/// `referral_fee` percent.
+pub fn split_referral(fees_amount: u64, referral_fee: u8) -> (u64, u64) {
+    let referral_commission = fees_amount
+        .saturating_mul(referral_fee as u64)
+        .saturating_div(100);
+    (
+        referral_commission,
+        fees_amount.saturating_sub(referral_commission),
+    )
+}
+
+/// Splits booster fees into `(buyback_funding, remaining_fees)` by
+/// `buyback_share` percent. Applied before the referral split.
+pub fn split_buyback(fees_amount: u64, buyback_share: u8) -> (u64, u64) {
+    let buyback_amount = fees_amount
+        .saturating_mul(buyback_share as u64)
+        .saturating_div(100);
+    (buyback_amount, fees_amount.saturating_sub(buyback_amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The burn/referral arithmetic handle_fee_transfers used before it moved here.
+    fn baseline_split(total_amount: u64, burn_rate: u8, referral_fee: u8) -> (u64, u64, u64) {
+        let burn_amount = total_amount
+            .saturating_mul(burn_rate as u64)
+            .saturating_div(100);
+        let fees_amount = total_amount.saturating_sub(burn_amount);
+        let referral_commission = fees_amount
+            .saturating_mul(referral_fee as u64)
+            .saturating_div(100);
+        let protocol_fee = fees_amount.saturating_sub(referral_commission);
+        (burn_amount, referral_commission, protocol_fee)
+    }
+
+    #[test]
+    fn split_burn_rounds_the_burn_down() {
+        assert_eq!(split_burn(999, 80), (799, 200));
+        assert_eq!(split_burn(1, 80), (0, 1));
+        assert_eq!(split_burn(100_000_000, 80), (80_000_000, 20_000_000));
+        assert_eq!(split_burn(12_345, 0), (0, 12_345));
+        assert_eq!(split_burn(12_345, 100), (12_345, 0));
+    }
+
+    #[test]
+    fn split_referral_rounds_the_commission_down() {
+        assert_eq!(split_referral(201, 10), (20, 181));
+        assert_eq!(split_referral(9, 10), (0, 9));
+        assert_eq!(split_referral(20_000_000, 100), (20_000_000, 0));
+        assert_eq!(split_referral(20_000_000, 0), (0, 20_000_000));
+    }
+
+    #[test]
+    fn split_buyback_rounds_the_share_down() {
+        assert_eq!(split_buyback(199, 50), (99, 100));
+        assert_eq!(split_buyback(199, 0), (0, 199));
+    }
+
+    #[test]
+    fn splits_match_baseline_and_never_lose_tokens() {
+        let amounts = [
+            0,
+            1,
+            7,
+            99,
+            100,
+            101,
+            999,
+            100_000_000,
+            u64::MAX / 100,
+            u64::MAX,
+        ];
+        for total_amount in amounts {
+            for burn_rate in [0u8, 1, 33, 80, 99, 100] {
+                for referral_fee in [0u8, 1, 10, 50, 100] {
+                    let (burn, fees) = split_burn(total_amount, burn_rate);
+                    let (commission, protocol_fee) = split_referral(fees, referral_fee);
+                    assert_eq!(
+                        (burn, commission, protocol_fee),
+                        baseline_split(total_amount, burn_rate, referral_fee)
+                    );
+                    // Saturation only kicks in near u64::MAX; below it nothing is lost
+                    if total_amount <= u64::MAX / 100 {
+                        assert_eq!(burn + commission + protocol_fee, total_amount);
+                    }
+                }
+            }
+        }
+    }
+}
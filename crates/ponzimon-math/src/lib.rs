@@ -0,0 +1,27 @@
+//! Game math shared by the on-chain program and off-chain tools.
+//!
+//! Everything here is a pure function over plain integers: no accounts, no
+//! clock, no randomness source. The program feeds in its `GlobalState`
+//! fields and slot-hash bytes; the simulator feeds in synthetic ones. Keeping
+//! a single copy means a tweak to the reward or odds formulas can't drift
+//! between what the chain does and what we model.
+#![no_std]
+
+extern crate alloc;
+
+pub mod booster;
+pub mod constants;
+pub mod farm;
+pub mod fees;
+pub mod loadout;
+pub mod pool;
+pub mod recycle;
+
+pub use booster::{apply_pity, pick_index, roll_booster_rarity, BOOSTER_RARITY_PER_MILLE};
+pub use farm::{check_stake, StakeError};
+pub use fees::{split_burn, split_buyback, split_referral};
+pub use loadout::{optimal_loadout, Loadout, LoadoutCard, LoadoutLimits};
+pub use pool::{pending_rewards, remaining_supply, update_pool, RewardPool};
+pub use recycle::{
+    recycle_roll_bps, recycle_upgrades, upgrade_odds_bps, BPS_DENOMINATOR, DEFAULT_UPGRADE_ODDS_BPS,
+};
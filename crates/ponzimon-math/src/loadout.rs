@@ -0,0 +1,318 @@
+//! Best staked subset: maximize hashpower within farm slots and berry capacity.
+//!
+//! This is a 0/1 knapsack with a cardinality limit, solved with a DP over
+//! (cards used, berries used). Two reductions keep the table small: at most
+//! `slots` cards of any one berry cost can ever be useful, and berry costs
+//! and capacity are divided by their gcd. Inputs still over the caller's
+//! limits get a greedy loadout instead.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadoutCard {
+    pub hashpower: u64,
+    pub berry_consumption: u64,
+}
+
+/// Caps on the DP so callers with a fixed heap and compute budget fall back
+/// to the greedy loadout instead of running out mid-way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadoutLimits {
+    /// (slots + 1) * (reduced capacity + 1) table entries, 8 bytes each
+    pub max_cells: usize,
+    /// cards * cells, the number of DP steps and of decision bits kept
+    pub max_work: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loadout {
+    /// Indices into the input cards, ascending
+    pub cards: Vec<usize>,
+    /// False when the DP was over the limits and the greedy fallback was used
+    pub exact: bool,
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Picks a subset of `cards` using at most `slots` cards and `berry_capacity`
+/// berries. The subset has maximal total hashpower when the DP fits `limits`;
+/// otherwise it is the better of two greedy fills (see `greedy_loadout`).
+pub fn optimal_loadout(
+    cards: &[LoadoutCard],
+    slots: usize,
+    berry_capacity: u64,
+    limits: LoadoutLimits,
+) -> Loadout {
+    // Cards that fit on their own, grouped by berry cost with the strongest
+    // first; only the first `slots` of each group can matter.
+    let mut candidates: Vec<usize> = (0..cards.len())
+        .filter(|&i| cards[i].berry_consumption <= berry_capacity && cards[i].hashpower > 0)
+        .collect();
+    candidates.sort_by(|&a, &b| {
+        cards[a]
+            .berry_consumption
+            .cmp(&cards[b].berry_consumption)
+            .then(cards[b].hashpower.cmp(&cards[a].hashpower))
+            .then(a.cmp(&b))
+    });
+    let mut kept = 0;
+    for i in 0..candidates.len() {
+        let same_cost_before = candidates[..kept]
+            .iter()
+            .rev()
+            .take_while(|&&c| cards[c].berry_consumption == cards[candidates[i]].berry_consumption)
+            .count();
+        if same_cost_before < slots {
+            candidates[kept] = candidates[i];
+            kept += 1;
+        }
+    }
+    candidates.truncate(kept);
+
+    let k_max = slots.min(candidates.len());
+    if k_max == 0 {
+        return Loadout {
+            cards: Vec::new(),
+            exact: true,
+        };
+    }
+
+    let unit = candidates
+        .iter()
+        .fold(0, |g, &c| gcd(g, cards[c].berry_consumption))
+        .max(1);
+    let weight_sum: u64 = candidates
+        .iter()
+        .map(|&c| cards[c].berry_consumption / unit)
+        .sum();
+    let b_max = (berry_capacity / unit).min(weight_sum) as usize;
+
+    let cells = (k_max + 1).saturating_mul(b_max + 1);
+    let work = candidates.len().saturating_mul(cells);
+    if cells > limits.max_cells || work > limits.max_work {
+        return Loadout {
+            cards: greedy_loadout(cards, &candidates, slots, berry_capacity),
+            exact: false,
+        };
+    }
+
+    // best[k][b]: max hashpower with at most k cards and b berry units.
+    // took[i][k][b]: whether item i improved best[k][b] when it was added.
+    let mut best = vec![0u64; cells];
+    let mut took = vec![0u64; work.div_ceil(64)];
+    for (i, &card) in candidates.iter().enumerate() {
+        let weight = (cards[card].berry_consumption / unit) as usize;
+        let hashpower = cards[card].hashpower;
+        for k in (1..=k_max).rev() {
+            for b in (weight..=b_max).rev() {
+                let with_card = best[(k - 1) * (b_max + 1) + b - weight] + hashpower;
+                let cell = k * (b_max + 1) + b;
+                if with_card > best[cell] {
+                    best[cell] = with_card;
+                    let bit = i * cells + cell;
+                    took[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+    }
+
+    let (mut k, mut b) = (k_max, b_max);
+    let mut chosen = Vec::new();
+    for (i, &card) in candidates.iter().enumerate().rev() {
+        let bit = i * cells + k * (b_max + 1) + b;
+        if took[bit / 64] & (1 << (bit % 64)) != 0 {
+            chosen.push(card);
+            k -= 1;
+            b -= (cards[card].berry_consumption / unit) as usize;
+        }
+    }
+    chosen.sort_unstable();
+    Loadout {
+        cards: chosen,
+        exact: true,
+    }
+}
+
+/// Fills slots in order of hashpower per berry, and separately in order of
+/// raw hashpower, skipping cards that no longer fit; returns whichever fill
+/// has more hashpower. The first wins when berries are scarce, the second
+/// when slots are.
+fn greedy_loadout(
+    cards: &[LoadoutCard],
+    candidates: &[usize],
+    slots: usize,
+    berry_capacity: u64,
+) -> Vec<usize> {
+    let fill = |order: &[usize]| {
+        let mut chosen = Vec::new();
+        let (mut berries, mut hashpower) = (0u64, 0u64);
+        for &card in order {
+            if chosen.len() == slots {
+                break;
+            }
+            if berries.saturating_add(cards[card].berry_consumption) <= berry_capacity {
+                berries += cards[card].berry_consumption;
+                hashpower = hashpower.saturating_add(cards[card].hashpower);
+                chosen.push(card);
+            }
+        }
+        chosen.sort_unstable();
+        (hashpower, chosen)
+    };
+
+    let mut by_ratio = candidates.to_vec();
+    // a.hp / a.berries > b.hp / b.berries, cross-multiplied; free cards first
+    by_ratio.sort_by(|&a, &b| {
+        let lhs = cards[a].hashpower as u128 * cards[b].berry_consumption as u128;
+        let rhs = cards[b].hashpower as u128 * cards[a].berry_consumption as u128;
+        rhs.cmp(&lhs)
+            .then(cards[b].hashpower.cmp(&cards[a].hashpower))
+            .then(a.cmp(&b))
+    });
+    let mut by_hashpower = candidates.to_vec();
+    by_hashpower.sort_by(|&a, &b| {
+        cards[b]
+            .hashpower
+            .cmp(&cards[a].hashpower)
+            .then(cards[a].berry_consumption.cmp(&cards[b].berry_consumption))
+            .then(a.cmp(&b))
+    });
+
+    let (ratio_hashpower, ratio_chosen) = fill(&by_ratio);
+    let (raw_hashpower, raw_chosen) = fill(&by_hashpower);
+    if raw_hashpower > ratio_hashpower {
+        raw_chosen
+    } else {
+        ratio_chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNLIMITED: LoadoutLimits = LoadoutLimits {
+        max_cells: usize::MAX,
+        max_work: usize::MAX,
+    };
+
+    fn card(hashpower: u64, berry_consumption: u64) -> LoadoutCard {
+        LoadoutCard {
+            hashpower,
+            berry_consumption,
+        }
+    }
+
+    /// (hashpower, berries) of a subset, asserting it is a valid loadout.
+    fn totals(cards: &[LoadoutCard], chosen: &[usize], slots: usize, capacity: u64) -> (u64, u64) {
+        assert!(chosen.len() <= slots);
+        assert!(chosen.windows(2).all(|w| w[0] < w[1]));
+        let hashpower = chosen.iter().map(|&i| cards[i].hashpower).sum();
+        let berries = chosen.iter().map(|&i| cards[i].berry_consumption).sum();
+        assert!(berries <= capacity);
+        (hashpower, berries)
+    }
+
+    fn brute_force(cards: &[LoadoutCard], slots: usize, capacity: u64) -> u64 {
+        (0u32..1 << cards.len())
+            .filter(|mask| mask.count_ones() as usize <= slots)
+            .filter_map(|mask| {
+                let picked = (0..cards.len()).filter(|i| mask & (1 << i) != 0);
+                let berries: u64 = picked.clone().map(|i| cards[i].berry_consumption).sum();
+                (berries <= capacity).then(|| picked.map(|i| cards[i].hashpower).sum())
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn matches_brute_force_on_small_inputs() {
+        // Small xorshift so the cases are varied but reproducible
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % bound
+        };
+        for _ in 0..300 {
+            let count = 1 + next(10) as usize;
+            let cards: Vec<LoadoutCard> =
+                (0..count).map(|_| card(next(50), 1 + next(12))).collect();
+            let slots = next(count as u64 + 2) as usize;
+            let capacity = next(40);
+
+            let loadout = optimal_loadout(&cards, slots, capacity, UNLIMITED);
+            assert!(loadout.exact);
+            let (hashpower, _) = totals(&cards, &loadout.cards, slots, capacity);
+            assert_eq!(hashpower, brute_force(&cards, slots, capacity));
+        }
+    }
+
+    #[test]
+    fn divides_berry_costs_by_their_gcd() {
+        // 6 berry units of 1_000 each; without the reduction the table would
+        // be 3 * 6_001 cells
+        let cards = [
+            card(10, 3_000),
+            card(7, 2_000),
+            card(6, 2_000),
+            card(4, 1_000),
+        ];
+        let limits = LoadoutLimits {
+            max_cells: 3 * 7,
+            max_work: 4 * 3 * 7,
+        };
+        let loadout = optimal_loadout(&cards, 2, 6_500, limits);
+        assert!(loadout.exact);
+        assert_eq!(loadout.cards, [0, 1]);
+    }
+
+    #[test]
+    fn keeps_only_slots_cards_per_berry_cost() {
+        // 100 cards of one cost, but only the 2 strongest can ever be staked,
+        // so the work is 2 candidates * 3 * 3 cells rather than 100 * 3 * 3
+        let cards: Vec<LoadoutCard> = (0..100).map(|i| card(i, 5)).collect();
+        let limits = LoadoutLimits {
+            max_cells: 9,
+            max_work: 18,
+        };
+        let loadout = optimal_loadout(&cards, 2, 1_000, limits);
+        assert!(loadout.exact);
+        assert_eq!(loadout.cards, [98, 99]);
+    }
+
+    #[test]
+    fn skips_cards_that_cannot_contribute() {
+        let cards = [card(0, 1), card(50, 11), card(5, 4)];
+        let loadout = optimal_loadout(&cards, 3, 10, UNLIMITED);
+        assert_eq!(loadout.cards, [2]);
+        assert!(optimal_loadout(&cards, 0, 10, UNLIMITED).cards.is_empty());
+    }
+
+    #[test]
+    fn falls_back_to_greedy_over_the_limits() {
+        let tiny = LoadoutLimits {
+            max_cells: 1,
+            max_work: 1,
+        };
+
+        // Berries are scarce: hashpower per berry wins
+        let cards = [card(100, 10), card(60, 5), card(55, 5)];
+        let loadout = optimal_loadout(&cards, 3, 10, tiny);
+        assert!(!loadout.exact);
+        assert_eq!(loadout.cards, [1, 2]);
+
+        // Slots are scarce: raw hashpower wins
+        let cards = [card(3, 1), card(2, 1), card(90, 8)];
+        let loadout = optimal_loadout(&cards, 1, 10, tiny);
+        assert!(!loadout.exact);
+        assert_eq!(loadout.cards, [2]);
+    }
+}
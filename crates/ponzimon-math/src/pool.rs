@@ -0,0 +1,312 @@
+//! Global reward accumulator (`update_pool`) and per-player pending rewards.
+
+/// The `GlobalState` fields `update_pool` reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RewardPool {
+    pub start_slot: u64,
+    pub last_reward_slot: u64,
+    pub reward_rate: u64,
+    pub total_hashpower: u64,
+    pub total_supply: u64,
+    pub cumulative_rewards: u64,
+    pub burned_tokens: u64,
+    pub dust_threshold_divisor: u64,
+    pub acc_tokens_per_hashpower: u128,
+}
+
+/// Supply that can still be emitted: total supply minus what is in
+/// circulation (minted rewards net of burns).
+pub fn remaining_supply(total_supply: u64, cumulative_rewards: u64, burned_tokens: u64) -> u64 {
+    let minted_minus_burn = cumulative_rewards.saturating_sub(burned_tokens);
+    total_supply.saturating_sub(minted_minus_burn)
+}
+
+impl RewardPool {
+    pub fn remaining_supply(&self) -> u64 {
+        remaining_supply(
+            self.total_supply,
+            self.cumulative_rewards,
+            self.burned_tokens,
+        )
+    }
+}
+
+/// Advances the accumulator to `slot_now`, emitting `reward_rate` per slot
+/// (clamped to the remaining supply) across `total_hashpower`. Once the
+/// remaining supply falls to the dust threshold the rate is zeroed for good.
+pub fn update_pool(pool: &mut RewardPool, slot_now: u64, acc_scale: u128) {
+    // Security: If the current slot is before the designated start slot,
+    // no rewards should be processed.
+    if slot_now < pool.start_slot {
+        pool.last_reward_slot = pool.start_slot;
+        return;
+    }
+
+    if slot_now <= pool.last_reward_slot || pool.total_hashpower == 0 {
+        pool.last_reward_slot = slot_now;
+        return;
+    }
+    let rate_now = pool.reward_rate;
+
+    /* remaining supply after accounting for burns */
+    let remaining_supply = pool.remaining_supply();
+
+    // A zero divisor disables the dust threshold rather than dividing by zero
+    let dust_threshold = pool
+        .total_supply
+        .checked_div(pool.dust_threshold_divisor)
+        .unwrap_or(0);
+    // Check if we're close to depleting the supply
+    if remaining_supply <= dust_threshold || rate_now == 0 {
+        // Then set rate to zero to prevent future mining
+        pool.reward_rate = 0;
+        pool.last_reward_slot = slot_now;
+        return;
+    }
+
+    let slots_elapsed = (slot_now - pool.last_reward_slot) as u128;
+    let mut reward = slots_elapsed.saturating_mul(rate_now as u128);
+    reward = reward.min(remaining_supply as u128); // clamp to cap
+
+    pool.acc_tokens_per_hashpower += reward * acc_scale / pool.total_hashpower as u128;
+    pool.cumulative_rewards = pool.cumulative_rewards.saturating_add(reward as u64);
+
+    pool.last_reward_slot = slot_now;
+}
+
+/// Rewards accrued by `hashpower` between two accumulator readings, clamped
+/// to the remaining supply. `acc_now` must come from an up-to-date pool.
+pub fn pending_rewards(
+    hashpower: u64,
+    acc_now: u128,
+    acc_last: u128,
+    remaining_supply: u64,
+    acc_scale: u128,
+) -> u64 {
+    let pending_u128 =
+        (hashpower as u128).saturating_mul(acc_now.saturating_sub(acc_last)) / acc_scale;
+    let pending = pending_u128.min(u64::MAX as u128) as u64;
+
+    // Clamp pending to remaining supply
+    pending.min(remaining_supply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::ACC_SCALE;
+
+    /// update_pool as the program ran it inline before it moved here, kept verbatim.
+    #[allow(clippy::manual_checked_ops, clippy::manual_saturating_arithmetic)]
+    fn baseline_update_pool(pool: &mut RewardPool, slot_now: u64) {
+        if slot_now < pool.start_slot {
+            pool.last_reward_slot = pool.start_slot;
+            return;
+        }
+        if slot_now <= pool.last_reward_slot || pool.total_hashpower == 0 {
+            pool.last_reward_slot = slot_now;
+            return;
+        }
+        let rate_now = pool.reward_rate;
+        let minted_minus_burn = pool.cumulative_rewards.saturating_sub(pool.burned_tokens);
+        let remaining_supply = pool.total_supply.saturating_sub(minted_minus_burn);
+        let dust_threshold = if pool.dust_threshold_divisor > 0 {
+            pool.total_supply / pool.dust_threshold_divisor
+        } else {
+            0
+        };
+        if remaining_supply <= dust_threshold || rate_now == 0 {
+            pool.reward_rate = 0;
+            pool.last_reward_slot = slot_now;
+            return;
+        }
+        let slots_elapsed = (slot_now - pool.last_reward_slot) as u128;
+        let mut reward = slots_elapsed
+            .checked_mul(rate_now as u128)
+            .unwrap_or(u128::MAX);
+        reward = reward.min(remaining_supply as u128);
+        pool.acc_tokens_per_hashpower += reward * ACC_SCALE / pool.total_hashpower as u128;
+        pool.cumulative_rewards = pool.cumulative_rewards.saturating_add(reward as u64);
+        pool.last_reward_slot = slot_now;
+    }
+
+    fn base_pool() -> RewardPool {
+        RewardPool {
+            start_slot: 100,
+            last_reward_slot: 100,
+            reward_rate: 1_000_000,
+            total_hashpower: 3_000,
+            total_supply: 21_000_000_000_000,
+            cumulative_rewards: 0,
+            burned_tokens: 0,
+            dust_threshold_divisor: 1_000,
+            acc_tokens_per_hashpower: 0,
+        }
+    }
+
+    #[test]
+    fn update_pool_matches_baseline() {
+        let cases = [
+            (base_pool(), 50),
+            (base_pool(), 100),
+            (base_pool(), 1_234),
+            (
+                RewardPool {
+                    total_hashpower: 0,
+                    ..base_pool()
+                },
+                500,
+            ),
+            (
+                RewardPool {
+                    reward_rate: 0,
+                    ..base_pool()
+                },
+                500,
+            ),
+            (
+                RewardPool {
+                    cumulative_rewards: 20_999_999_000_000,
+                    burned_tokens: 7,
+                    ..base_pool()
+                },
+                10_000_000,
+            ),
+            (
+                RewardPool {
+                    cumulative_rewards: 20_979_000_000_000,
+                    ..base_pool()
+                },
+                200,
+            ),
+            (
+                RewardPool {
+                    dust_threshold_divisor: 0,
+                    cumulative_rewards: 20_999_999_999_990,
+                    ..base_pool()
+                },
+                200,
+            ),
+            (
+                RewardPool {
+                    reward_rate: u64::MAX,
+                    ..base_pool()
+                },
+                u64::MAX,
+            ),
+        ];
+
+        for (start, slot) in cases {
+            let mut expected = start;
+            baseline_update_pool(&mut expected, slot);
+            let mut actual = start;
+            update_pool(&mut actual, slot, ACC_SCALE);
+            assert_eq!(actual, expected, "slot {slot} from {start:?}");
+        }
+    }
+
+    #[test]
+    fn update_pool_emits_rate_per_slot_across_hashpower() {
+        let mut pool = base_pool();
+        update_pool(&mut pool, 110, ACC_SCALE);
+
+        assert_eq!(pool.cumulative_rewards, 10_000_000);
+        assert_eq!(
+            pool.acc_tokens_per_hashpower,
+            10_000_000 * ACC_SCALE / 3_000
+        );
+        assert_eq!(pool.last_reward_slot, 110);
+    }
+
+    #[test]
+    fn update_pool_clamps_reward_to_remaining_supply() {
+        let mut pool = RewardPool {
+            total_supply: 1_000_000,
+            cumulative_rewards: 900_000,
+            burned_tokens: 100_000,
+            dust_threshold_divisor: 1_000,
+            ..base_pool()
+        };
+        // Burns return supply: 1_000_000 - (900_000 - 100_000) remains
+        assert_eq!(pool.remaining_supply(), 200_000);
+
+        update_pool(&mut pool, 1_100, ACC_SCALE);
+
+        assert_eq!(pool.cumulative_rewards, 1_100_000);
+        assert_eq!(pool.acc_tokens_per_hashpower, 200_000 * ACC_SCALE / 3_000);
+        assert_eq!(pool.remaining_supply(), 0);
+        assert_eq!(pool.reward_rate, 1_000_000);
+    }
+
+    #[test]
+    fn update_pool_stops_for_good_at_dust_threshold() {
+        // Threshold is 1_000_000 / 1_000 = 1_000, and exactly that much remains
+        let mut pool = RewardPool {
+            total_supply: 1_000_000,
+            cumulative_rewards: 999_000,
+            ..base_pool()
+        };
+        update_pool(&mut pool, 200, ACC_SCALE);
+
+        assert_eq!(pool.reward_rate, 0);
+        assert_eq!(pool.cumulative_rewards, 999_000);
+        assert_eq!(pool.acc_tokens_per_hashpower, 0);
+        assert_eq!(pool.last_reward_slot, 200);
+
+        // One token above the threshold still emits, clamped to what is left
+        let mut pool = RewardPool {
+            total_supply: 1_000_000,
+            cumulative_rewards: 998_999,
+            ..base_pool()
+        };
+        update_pool(&mut pool, 200, ACC_SCALE);
+        assert_eq!(pool.cumulative_rewards, 1_000_000);
+    }
+
+    #[test]
+    fn update_pool_before_start_only_pins_last_reward_slot() {
+        let mut pool = RewardPool {
+            last_reward_slot: 0,
+            ..base_pool()
+        };
+        update_pool(&mut pool, 99, ACC_SCALE);
+
+        assert_eq!(pool.last_reward_slot, 100);
+        assert_eq!(pool.cumulative_rewards, 0);
+    }
+
+    #[test]
+    fn pending_rewards_scales_hashpower_by_accumulator_delta() {
+        let mut pool = base_pool();
+        update_pool(&mut pool, 110, ACC_SCALE);
+        let acc_now = pool.acc_tokens_per_hashpower;
+
+        // A third of the hashpower earns a third of the emission, rounded down
+        assert_eq!(
+            pending_rewards(1_000, acc_now, 0, pool.remaining_supply(), ACC_SCALE),
+            3_333_333
+        );
+        assert_eq!(
+            pending_rewards(3_000, acc_now, 0, pool.remaining_supply(), ACC_SCALE),
+            9_999_999
+        );
+        // Nothing accrued since the last reading
+        assert_eq!(
+            pending_rewards(1_000, acc_now, acc_now, u64::MAX, ACC_SCALE),
+            0
+        );
+        // A stale reading ahead of the accumulator never underflows
+        assert_eq!(pending_rewards(1_000, 0, acc_now, u64::MAX, ACC_SCALE), 0);
+    }
+
+    #[test]
+    fn pending_rewards_is_clamped_to_remaining_supply() {
+        let acc_now = 10 * ACC_SCALE;
+        assert_eq!(
+            pending_rewards(1_000, acc_now, 0, u64::MAX, ACC_SCALE),
+            10_000
+        );
+        assert_eq!(pending_rewards(1_000, acc_now, 0, 2_500, ACC_SCALE), 2_500);
+        assert_eq!(pending_rewards(1_000, acc_now, 0, 0, ACC_SCALE), 0);
+    }
+}
@@ -0,0 +1,29 @@
+//! Recycle upgrade rolls.
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// The `i`-th recycle roll from a 32-byte slot hash, in basis points.
+/// Each card reads two bytes, wrapping around the hash.
+pub fn recycle_roll_bps(random_value: &[u8], i: usize) -> u64 {
+    let random_index = (i * 2) % random_value.len();
+    let random_u16 = u16::from_le_bytes([
+        random_value[random_index],
+        random_value[(random_index + 1) % random_value.len()],
+    ]);
+    // Maps the full u16 range onto 0..10_000 without bias towards low values
+    random_u16 as u64 * BPS_DENOMINATOR / (u16::MAX as u64 + 1)
+}
+
+/// Per-rarity upgrade odds a new odds table starts with.
+pub const DEFAULT_UPGRADE_ODDS_BPS: u16 = 2_000;
+
+/// Upgrade odds in basis points for a card of `rarity`. Rarities past the
+/// end of the table never upgrade.
+pub fn upgrade_odds_bps(odds_table: &[u16], rarity: u8) -> u64 {
+    odds_table.get(rarity as usize).copied().unwrap_or(0) as u64
+}
+
+/// Whether a card of `rarity` upgrades on a roll of `random_bps`.
+pub fn recycle_upgrades(odds_table: &[u16], rarity: u8, random_bps: u64) -> bool {
+    random_bps < upgrade_odds_bps(odds_table, rarity)
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use ponzimon_math::constants::RARE;
+use serde::{Deserialize, Serialize};
+
+/// Everything a run depends on. Every field has a default, so a config file
+/// only needs the parts being tuned plus the farm tiers, card catalog and
+/// starter cards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    pub seed: u64,
+    pub players: u32,
+    /// Length of the run in slots
+    pub slots: u64,
+    /// Players join uniformly at random within the first this-many slots
+    pub join_window_slots: u64,
+    /// How often every player settles and acts
+    pub action_interval_slots: u64,
+    /// How often a timeline sample is recorded
+    pub sample_interval_slots: u64,
+    pub economy: EconomyConfig,
+    /// Farm tiers indexed by farm_type, as listed in the on-chain FarmConfig
+    pub farms: Vec<FarmTier>,
+    pub catalog: Vec<SimCard>,
+    pub starter_card_ids: Vec<u16>,
+    /// Relative share of players following each strategy
+    pub strategies: HashMap<Strategy, u32>,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            players: 1_000,
+            slots: 6_480_000,               // ~30 days at 400ms slots
+            join_window_slots: 1_512_000,   // ~7 days
+            action_interval_slots: 9_000,   // ~1 hour
+            sample_interval_slots: 216_000, // ~1 day
+            economy: EconomyConfig::default(),
+            farms: Vec::new(),
+            catalog: Vec::new(),
+            starter_card_ids: Vec::new(),
+            strategies: [
+                (Strategy::Hodl, 1),
+                (Strategy::Booster, 1),
+                (Strategy::Upgrader, 1),
+                (Strategy::Balanced, 1),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+/// GlobalState parameters. Defaults mirror initialize_program; total_supply
+/// and reward_rate are initialize arguments, so the defaults are placeholders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EconomyConfig {
+    pub total_supply: u64,
+    pub reward_rate: u64,
+    pub burn_rate: u8,
+    pub dust_threshold_divisor: u64,
+    pub booster_pack_cost_microtokens: u64,
+    pub initial_farm_purchase_fee_lamports: u64,
+    pub pity_rarity: u8,
+    pub pity_threshold: u8,
+    pub recycle_upgrade_odds_bps: Vec<u16>,
+    /// Token base units per SOL, used to fold SOL spend into ROI. 0 leaves
+    /// SOL out and reports token-only ROI.
+    pub tokens_per_sol: u64,
+}
+
+impl Default for EconomyConfig {
+    fn default() -> Self {
+        Self {
+            total_supply: 21_000_000_000_000,
+            reward_rate: 1_000_000,
+            burn_rate: 80,
+            dust_threshold_divisor: 1000,
+            booster_pack_cost_microtokens: 100_000_000,
+            initial_farm_purchase_fee_lamports: 300_000_000,
+            pity_rarity: RARE,
+            pity_threshold: 10,
+            recycle_upgrade_odds_bps: vec![2_000; 6],
+            tokens_per_sol: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FarmTier {
+    pub total_cards: u64,
+    pub berry_capacity: u64,
+    pub cost: u64,
+}
+
+/// A card catalog entry, as listed in the on-chain CardCatalog.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SimCard {
+    pub id: u16,
+    pub rarity: u8,
+    pub hashpower: u64,
+    pub berry_consumption: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strategy {
+    /// Stakes the starter cards and only ever claims
+    Hodl,
+    /// Spends every claim on boosters
+    Booster,
+    /// Spends every claim on farm upgrades
+    Upgrader,
+    /// Upgrades when capacity is the bottleneck, otherwise opens boosters and
+    /// recycles commons when the inventory fills up
+    Balanced,
+}
@@ -0,0 +1,79 @@
+//! `ponzimon-sim` — runs thousands of synthetic players against the program's
+//! reward, booster and recycle math and reports supply depletion, ROI per farm
+//! tier and burn totals as JSON.
+
+mod config;
+mod report;
+mod sim;
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use crate::config::SimConfig;
+use crate::sim::World;
+
+#[derive(Parser)]
+#[command(
+    name = "ponzimon-sim",
+    about = "Offline economic simulator for ponzimon"
+)]
+struct Cli {
+    /// JSON SimConfig; omitted fields keep their defaults, but farms, catalog and starter cards are required
+    #[arg(long)]
+    config: PathBuf,
+    #[arg(long)]
+    seed: Option<u64>,
+    #[arg(long)]
+    players: Option<u32>,
+    #[arg(long)]
+    slots: Option<u64>,
+    #[arg(long)]
+    reward_rate: Option<u64>,
+    /// Percent of every token payment that is burned
+    #[arg(long)]
+    burn_rate: Option<u8>,
+    #[arg(long)]
+    booster_cost: Option<u64>,
+    /// Leave the per-day timeline out of the report
+    #[arg(long)]
+    no_timeline: bool,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let data =
+        std::fs::read(&cli.config).with_context(|| format!("reading {}", cli.config.display()))?;
+    let mut config: SimConfig = serde_json::from_slice(&data)
+        .with_context(|| format!("parsing {}", cli.config.display()))?;
+
+    // Overrides for quick parameter sweeps without editing the file
+    if let Some(seed) = cli.seed {
+        config.seed = seed;
+    }
+    if let Some(players) = cli.players {
+        config.players = players;
+    }
+    if let Some(slots) = cli.slots {
+        config.slots = slots;
+    }
+    if let Some(reward_rate) = cli.reward_rate {
+        config.economy.reward_rate = reward_rate;
+    }
+    if let Some(burn_rate) = cli.burn_rate {
+        anyhow::ensure!(burn_rate <= 100, "burn rate is a percentage");
+        config.economy.burn_rate = burn_rate;
+    }
+    if let Some(booster_cost) = cli.booster_cost {
+        config.economy.booster_pack_cost_microtokens = booster_cost;
+    }
+
+    let mut report = World::new(&config)?.run();
+    if cli.no_timeline {
+        report.timeline.clear();
+    }
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use ponzimon_math::RewardPool;
+use serde::Serialize;
+
+use crate::config::{SimConfig, Strategy};
+use crate::sim::SimPlayer;
+
+/// Counters accumulated while the simulation runs.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Totals {
+    #[serde(skip)]
+    pub supply_depleted_at_slot: Option<u64>,
+    pub burned_by_boosters: u64,
+    pub burned_by_upgrades: u64,
+    pub protocol_fees_tokens: u64,
+    pub protocol_fees_lamports: u64,
+    pub boosters_opened: u64,
+    pub pity_triggers: u64,
+    pub farm_upgrades: u64,
+    pub cards_recycled: u64,
+    pub recycle_upgrades: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Sample {
+    pub slot: u64,
+    pub players: u32,
+    pub total_hashpower: u64,
+    pub reward_rate: u64,
+    pub cumulative_rewards: u64,
+    pub burned_tokens: u64,
+    pub remaining_supply: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SupplyReport {
+    pub total_supply: u64,
+    pub cumulative_rewards: u64,
+    pub burned_tokens: u64,
+    pub remaining_supply: u64,
+    pub final_reward_rate: u64,
+    /// First action step at which update_pool had zeroed the reward rate
+    pub depleted_at_slot: Option<u64>,
+}
+
+/// Averages over a group of players. ROI is `(rewards - spend) / spend`,
+/// with SOL converted at `tokens_per_sol` (left out when that is 0).
+#[derive(Debug, Default, Serialize)]
+pub struct GroupReport {
+    pub players: u32,
+    pub avg_hashpower: f64,
+    pub avg_rewards: f64,
+    pub avg_tokens_spent: f64,
+    pub avg_sol_spent_lamports: f64,
+    pub roi: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub seed: u64,
+    pub players: u32,
+    pub slots: u64,
+    pub supply: SupplyReport,
+    pub totals: Totals,
+    /// Keyed by the farm_type players ended the run on
+    pub farm_tiers: BTreeMap<u8, GroupReport>,
+    pub strategies: BTreeMap<Strategy, GroupReport>,
+    pub timeline: Vec<Sample>,
+}
+
+impl Report {
+    pub fn build(
+        config: &SimConfig,
+        pool: &RewardPool,
+        players: &[SimPlayer],
+        totals: Totals,
+        timeline: Vec<Sample>,
+    ) -> Self {
+        let joined: Vec<&SimPlayer> = players.iter().filter(|p| p.joined).collect();
+        let mut farm_tiers: BTreeMap<u8, Vec<&SimPlayer>> = BTreeMap::new();
+        let mut strategies: BTreeMap<Strategy, Vec<&SimPlayer>> = BTreeMap::new();
+        for &player in &joined {
+            farm_tiers.entry(player.farm_type).or_default().push(player);
+            strategies.entry(player.strategy).or_default().push(player);
+        }
+        let tokens_per_sol = config.economy.tokens_per_sol;
+
+        Self {
+            seed: config.seed,
+            players: joined.len() as u32,
+            slots: config.slots,
+            supply: SupplyReport {
+                total_supply: pool.total_supply,
+                cumulative_rewards: pool.cumulative_rewards,
+                burned_tokens: pool.burned_tokens,
+                remaining_supply: pool.remaining_supply(),
+                final_reward_rate: pool.reward_rate,
+                depleted_at_slot: totals.supply_depleted_at_slot,
+            },
+            totals,
+            farm_tiers: farm_tiers
+                .into_iter()
+                .map(|(tier, group)| (tier, group_report(&group, tokens_per_sol)))
+                .collect(),
+            strategies: strategies
+                .into_iter()
+                .map(|(strategy, group)| (strategy, group_report(&group, tokens_per_sol)))
+                .collect(),
+            timeline,
+        }
+    }
+}
+
+fn group_report(group: &[&SimPlayer], tokens_per_sol: u64) -> GroupReport {
+    if group.is_empty() {
+        return GroupReport::default();
+    }
+    let n = group.len() as f64;
+    let sum = |f: fn(&SimPlayer) -> u64| group.iter().map(|p| f(p) as f64).sum::<f64>();
+
+    let rewards = sum(|p| p.total_rewards);
+    let tokens_spent = sum(|p| p.tokens_spent);
+    let sol_spent = sum(|p| p.sol_spent);
+    let spend = tokens_spent + sol_spent * tokens_per_sol as f64 / 1e9;
+
+    GroupReport {
+        players: group.len() as u32,
+        avg_hashpower: sum(|p| p.hashpower) / n,
+        avg_rewards: rewards / n,
+        avg_tokens_spent: tokens_spent / n,
+        avg_sol_spent_lamports: sol_spent / n,
+        roi: (spend > 0.0).then(|| (rewards - spend) / spend),
+    }
+}
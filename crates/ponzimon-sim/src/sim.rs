@@ -0,0 +1,409 @@
+//! The simulation loop. Every player settles through `ponzimon_math` exactly
+//! as the program's settle_and_mint_rewards does, then acts per its strategy.
+//! Randomness comes from a seeded RNG shaped like a slot hash, so the booster
+//! and recycle rolls go through the same byte-slicing as on-chain.
+
+use anyhow::{ensure, Result};
+use ponzimon_math::constants::{ACC_SCALE, COMMON, MAX_CARDS_PER_PLAYER, MEGA_RARE};
+use ponzimon_math::{
+    apply_pity, pending_rewards, pick_index, recycle_roll_bps, recycle_upgrades,
+    roll_booster_rarity, split_burn, update_pool, RewardPool,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::config::{SimConfig, Strategy};
+use crate::report::{Report, Sample, Totals};
+
+const CARDS_PER_BOOSTER: usize = 5;
+
+pub struct SimPlayer {
+    pub strategy: Strategy,
+    join_slot: u64,
+    pub joined: bool,
+    pub farm_type: u8,
+    /// (catalog index, staked)
+    cards: Vec<(usize, bool)>,
+    berries: u64,
+    pub hashpower: u64,
+    last_acc_tokens_per_hashpower: u128,
+    balance: u64,
+    boosters_without_pity: u8,
+    pub total_rewards: u64,
+    pub tokens_spent: u64,
+    pub sol_spent: u64,
+}
+
+/// What a token payment went towards, for the burn breakdown.
+#[derive(Clone, Copy)]
+enum Spend {
+    Booster,
+    Upgrade,
+}
+
+pub struct World<'a> {
+    config: &'a SimConfig,
+    rng: StdRng,
+    pool: RewardPool,
+    pub players: Vec<SimPlayer>,
+    /// Catalog indices grouped by rarity
+    by_rarity: Vec<Vec<usize>>,
+    pub totals: Totals,
+}
+
+impl<'a> World<'a> {
+    pub fn new(config: &'a SimConfig) -> Result<Self> {
+        ensure!(!config.catalog.is_empty(), "config must list catalog cards");
+        ensure!(
+            !config.starter_card_ids.is_empty(),
+            "config must list starter_card_ids"
+        );
+        ensure!(
+            config.farms.len() > 1,
+            "config must list at least two farm tiers (copy them from the FarmConfig account)"
+        );
+        ensure!(
+            config.action_interval_slots > 0,
+            "action_interval_slots must be positive"
+        );
+
+        let mut by_rarity = vec![Vec::new(); MEGA_RARE as usize + 1];
+        for (index, card) in config.catalog.iter().enumerate() {
+            ensure!(
+                card.rarity <= MEGA_RARE,
+                "card {} has unknown rarity {}",
+                card.id,
+                card.rarity
+            );
+            by_rarity[card.rarity as usize].push(index);
+        }
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let strategies: Vec<(Strategy, u32)> = {
+            let mut weights: Vec<_> = config
+                .strategies
+                .iter()
+                .map(|(&strategy, &weight)| (strategy, weight))
+                .collect();
+            // HashMap order is random; sort so a seed reproduces the same run
+            weights.sort();
+            weights
+        };
+        ensure!(
+            strategies.iter().any(|&(_, weight)| weight > 0),
+            "at least one strategy needs a positive weight"
+        );
+
+        let players = (0..config.players)
+            .map(|_| SimPlayer {
+                strategy: strategies
+                    .choose_weighted(&mut rng, |&(_, weight)| weight)
+                    .map(|&(strategy, _)| strategy)
+                    .unwrap_or(Strategy::Hodl),
+                join_slot: rng.gen_range(0..=config.join_window_slots),
+                joined: false,
+                farm_type: 0,
+                cards: Vec::new(),
+                berries: 0,
+                hashpower: 0,
+                last_acc_tokens_per_hashpower: 0,
+                balance: 0,
+                boosters_without_pity: 0,
+                total_rewards: 0,
+                tokens_spent: 0,
+                sol_spent: 0,
+            })
+            .collect();
+
+        let economy = &config.economy;
+        Ok(Self {
+            config,
+            rng,
+            pool: RewardPool {
+                reward_rate: economy.reward_rate,
+                total_supply: economy.total_supply,
+                dust_threshold_divisor: economy.dust_threshold_divisor,
+                ..RewardPool::default()
+            },
+            players,
+            by_rarity,
+            totals: Totals::default(),
+        })
+    }
+
+    pub fn run(mut self) -> Report {
+        let mut timeline = Vec::new();
+        let mut order: Vec<usize> = (0..self.players.len()).collect();
+
+        let mut slot = 0;
+        while slot <= self.config.slots {
+            order.shuffle(&mut self.rng);
+            for &index in &order {
+                self.step(index, slot);
+            }
+
+            if self.totals.supply_depleted_at_slot.is_none()
+                && self.pool.reward_rate == 0
+                && self.pool.cumulative_rewards > 0
+            {
+                self.totals.supply_depleted_at_slot = Some(slot);
+            }
+            if slot % self.config.sample_interval_slots.max(1) < self.config.action_interval_slots {
+                timeline.push(self.sample(slot));
+            }
+            slot += self.config.action_interval_slots;
+        }
+
+        Report::build(
+            self.config,
+            &self.pool,
+            &self.players,
+            self.totals,
+            timeline,
+        )
+    }
+
+    fn sample(&self, slot: u64) -> Sample {
+        Sample {
+            slot,
+            players: self.players.iter().filter(|p| p.joined).count() as u32,
+            total_hashpower: self.pool.total_hashpower,
+            reward_rate: self.pool.reward_rate,
+            cumulative_rewards: self.pool.cumulative_rewards,
+            burned_tokens: self.pool.burned_tokens,
+            remaining_supply: self.pool.remaining_supply(),
+        }
+    }
+
+    fn step(&mut self, index: usize, slot: u64) {
+        if !self.players[index].joined {
+            if self.players[index].join_slot <= slot {
+                self.purchase_initial_farm(index, slot);
+            }
+            return;
+        }
+
+        self.settle(index, slot);
+        match self.players[index].strategy {
+            Strategy::Hodl => {}
+            Strategy::Booster => while self.open_booster(index) {},
+            Strategy::Upgrader => while self.upgrade_farm(index) {},
+            Strategy::Balanced => {
+                if self.capacity_bound(index) {
+                    self.upgrade_farm(index);
+                }
+                while self.open_booster(index) {}
+                self.recycle_commons(index);
+            }
+        }
+        self.restake(index);
+    }
+
+    fn purchase_initial_farm(&mut self, index: usize, slot: u64) {
+        update_pool(&mut self.pool, slot, ACC_SCALE);
+        let fee = self.config.economy.initial_farm_purchase_fee_lamports;
+        let starters: Vec<usize> = self
+            .config
+            .starter_card_ids
+            .iter()
+            .filter_map(|id| self.config.catalog.iter().position(|c| c.id == *id))
+            .collect();
+
+        let player = &mut self.players[index];
+        player.joined = true;
+        player.farm_type = 1;
+        player.cards = starters.into_iter().map(|card| (card, false)).collect();
+        player.last_acc_tokens_per_hashpower = self.pool.acc_tokens_per_hashpower;
+        player.sol_spent += fee;
+        self.totals.protocol_fees_lamports += fee;
+        self.restake(index);
+    }
+
+    /// Claims everything pending at `slot`, like settle_and_mint_rewards.
+    fn settle(&mut self, index: usize, slot: u64) {
+        update_pool(&mut self.pool, slot, ACC_SCALE);
+        let player = &mut self.players[index];
+        let pending = pending_rewards(
+            player.hashpower,
+            self.pool.acc_tokens_per_hashpower,
+            player.last_acc_tokens_per_hashpower,
+            self.pool.remaining_supply(),
+            ACC_SCALE,
+        );
+        player.last_acc_tokens_per_hashpower = self.pool.acc_tokens_per_hashpower;
+        player.balance += pending;
+        player.total_rewards += pending;
+    }
+
+    fn pay(&mut self, index: usize, amount: u64, spend: Spend) -> bool {
+        let player = &mut self.players[index];
+        if player.balance < amount {
+            return false;
+        }
+        player.balance -= amount;
+        player.tokens_spent += amount;
+
+        let (burn, fees) = split_burn(amount, self.config.economy.burn_rate);
+        self.pool.burned_tokens = self.pool.burned_tokens.saturating_add(burn);
+        self.totals.protocol_fees_tokens += fees;
+        match spend {
+            Spend::Booster => self.totals.burned_by_boosters += burn,
+            Spend::Upgrade => self.totals.burned_by_upgrades += burn,
+        }
+        true
+    }
+
+    fn upgrade_farm(&mut self, index: usize) -> bool {
+        let next = self.players[index].farm_type as usize + 1;
+        let Some(tier) = self.config.farms.get(next).copied() else {
+            return false;
+        };
+        if !self.pay(index, tier.cost, Spend::Upgrade) {
+            return false;
+        }
+        self.players[index].farm_type = next as u8;
+        self.totals.farm_upgrades += 1;
+        true
+    }
+
+    fn open_booster(&mut self, index: usize) -> bool {
+        if self.players[index].cards.len() + CARDS_PER_BOOSTER > MAX_CARDS_PER_PLAYER as usize {
+            return false;
+        }
+        if !self.pay(
+            index,
+            self.config.economy.booster_pack_cost_microtokens,
+            Spend::Booster,
+        ) {
+            return false;
+        }
+
+        let random_value: [u8; 32] = self.rng.gen();
+        let mut rarities = [0u8; CARDS_PER_BOOSTER];
+        let mut random_u32s = [0u32; CARDS_PER_BOOSTER];
+        for i in 0..CARDS_PER_BOOSTER {
+            let random_u32 = u32::from_le_bytes(random_value[i * 4..i * 4 + 4].try_into().unwrap());
+            random_u32s[i] = random_u32;
+            rarities[i] = roll_booster_rarity(random_u32);
+        }
+        let economy = &self.config.economy;
+        let player = &mut self.players[index];
+        if apply_pity(
+            &mut rarities,
+            &mut player.boosters_without_pity,
+            economy.pity_rarity,
+            economy.pity_threshold,
+        ) {
+            self.totals.pity_triggers += 1;
+        }
+
+        for (rarity, random_u32) in rarities.into_iter().zip(random_u32s) {
+            let cards = &self.by_rarity[rarity as usize];
+            if !cards.is_empty() {
+                player
+                    .cards
+                    .push((cards[pick_index(random_u32, cards.len())], false));
+            }
+        }
+        self.totals.boosters_opened += 1;
+        true
+    }
+
+    /// Recycles unstaked commons once the inventory can't fit another booster.
+    fn recycle_commons(&mut self, index: usize) {
+        let player = &self.players[index];
+        if player.cards.len() + CARDS_PER_BOOSTER <= MAX_CARDS_PER_PLAYER as usize {
+            return;
+        }
+        let recycled: Vec<usize> = player
+            .cards
+            .iter()
+            .enumerate()
+            .filter(|&(_, &(card, staked))| !staked && self.config.catalog[card].rarity == COMMON)
+            .map(|(position, _)| position)
+            .take(128)
+            .collect();
+        if recycled.is_empty() {
+            return;
+        }
+
+        let random_value: [u8; 32] = self.rng.gen();
+        let mut upgraded = Vec::new();
+        for (i, &position) in recycled.iter().enumerate() {
+            let rarity = self.config.catalog[player.cards[position].0].rarity;
+            let random_bps = recycle_roll_bps(&random_value, i);
+            if rarity >= MEGA_RARE
+                || !recycle_upgrades(
+                    &self.config.economy.recycle_upgrade_odds_bps,
+                    rarity,
+                    random_bps,
+                )
+            {
+                continue;
+            }
+            let cards = &self.by_rarity[rarity as usize + 1];
+            if !cards.is_empty() {
+                upgraded.push(cards[pick_index(self.rng.gen(), cards.len())]);
+            }
+        }
+
+        self.totals.cards_recycled += recycled.len() as u64;
+        self.totals.recycle_upgrades += upgraded.len() as u64;
+        let player = &mut self.players[index];
+        for &position in recycled.iter().rev() {
+            player.cards.remove(position);
+        }
+        player
+            .cards
+            .extend(upgraded.into_iter().map(|card| (card, false)));
+    }
+
+    /// Whether the farm, rather than the inventory, limits hashpower: some
+    /// unstaked card would fit if there were a free slot or berry capacity.
+    fn capacity_bound(&self, index: usize) -> bool {
+        let player = &self.players[index];
+        let tier = self.config.farms[player.farm_type as usize];
+        let staked_count = player.cards.iter().filter(|&&(_, staked)| staked).count() as u64;
+        player.cards.iter().any(|&(card, staked)| {
+            !staked
+                && (staked_count >= tier.total_cards
+                    || player.berries + self.config.catalog[card].berry_consumption
+                        > tier.berry_capacity)
+        })
+    }
+
+    /// Re-picks the staked set greedily by hashpower per berry, the same rule
+    /// the batch CLI's `stake` uses. Expects rewards already settled this slot.
+    fn restake(&mut self, index: usize) {
+        let catalog = &self.config.catalog;
+        let player = &mut self.players[index];
+        let tier = self.config.farms[player.farm_type as usize];
+
+        self.pool.total_hashpower -= player.hashpower;
+        player.hashpower = 0;
+        player.berries = 0;
+
+        let mut order: Vec<usize> = (0..player.cards.len()).collect();
+        order.sort_by(|&a, &b| {
+            let (a, b) = (&catalog[player.cards[a].0], &catalog[player.cards[b].0]);
+            // a.hp / a.berry > b.hp / b.berry, compared without division
+            (b.hashpower * a.berry_consumption.max(1))
+                .cmp(&(a.hashpower * b.berry_consumption.max(1)))
+        });
+
+        let mut staked = 0;
+        for position in order {
+            let card = &catalog[player.cards[position].0];
+            let fits = staked < tier.total_cards
+                && player.berries + card.berry_consumption <= tier.berry_capacity;
+            player.cards[position].1 = fits;
+            if fits {
+                staked += 1;
+                player.berries += card.berry_consumption;
+                player.hashpower += card.hashpower;
+            }
+        }
+        self.pool.total_hashpower += player.hashpower;
+    }
+}
@@ -0,0 +1,215 @@
+//! JavaScript bindings for `ponzimon_math`, built with
+//! `wasm-pack build crates/ponzimon-wasm --target web`.
+//!
+//! u64 values cross the boundary as `BigInt`; u128 accumulators as decimal
+//! strings, which is how the frontend's account decoders already hold them.
+
+use ponzimon_math::constants::{ACC_SCALE, MAX_CARDS_PER_PLAYER};
+use ponzimon_math::{pool, StakeError, BOOSTER_RARITY_PER_MILLE};
+use wasm_bindgen::prelude::*;
+
+fn parse_u128(name: &str, value: &str) -> Result<u128, JsError> {
+    value
+        .parse()
+        .map_err(|_| JsError::new(&format!("{name} is not a u128: {value}")))
+}
+
+/// The reward accumulator from GlobalState, advanced with the program's own
+/// update_pool.
+#[wasm_bindgen]
+pub struct RewardPool(pool::RewardPool);
+
+#[wasm_bindgen]
+impl RewardPool {
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        start_slot: u64,
+        last_reward_slot: u64,
+        reward_rate: u64,
+        total_hashpower: u64,
+        total_supply: u64,
+        cumulative_rewards: u64,
+        burned_tokens: u64,
+        dust_threshold_divisor: u64,
+        acc_tokens_per_hashpower: &str,
+    ) -> Result<RewardPool, JsError> {
+        Ok(Self(pool::RewardPool {
+            start_slot,
+            last_reward_slot,
+            reward_rate,
+            total_hashpower,
+            total_supply,
+            cumulative_rewards,
+            burned_tokens,
+            dust_threshold_divisor,
+            acc_tokens_per_hashpower: parse_u128(
+                "acc_tokens_per_hashpower",
+                acc_tokens_per_hashpower,
+            )?,
+        }))
+    }
+
+    /// Runs update_pool up to `slot`, as the next settling instruction would.
+    pub fn update(&mut self, slot: u64) {
+        ponzimon_math::update_pool(&mut self.0, slot, ACC_SCALE);
+    }
+
+    /// Claimable rewards for a player, as settle_and_mint_rewards computes
+    /// them: the newly earned amount plus Player.accrued_rewards held back by
+    /// the claim cooldown. Call `update` with the current slot first.
+    #[wasm_bindgen(js_name = pendingRewards)]
+    pub fn pending_rewards(
+        &self,
+        player_hashpower: u64,
+        player_last_acc_tokens_per_hashpower: &str,
+        player_accrued_rewards: u64,
+    ) -> Result<u64, JsError> {
+        Ok(ponzimon_math::pending_rewards(
+            player_hashpower,
+            self.0.acc_tokens_per_hashpower,
+            parse_u128(
+                "last_acc_tokens_per_hashpower",
+                player_last_acc_tokens_per_hashpower,
+            )?,
+            self.0.remaining_supply(),
+            ACC_SCALE,
+        )
+        .saturating_add(player_accrued_rewards))
+    }
+
+    #[wasm_bindgen(js_name = remainingSupply)]
+    pub fn remaining_supply(&self) -> u64 {
+        self.0.remaining_supply()
+    }
+
+    #[wasm_bindgen(getter, js_name = rewardRate)]
+    pub fn reward_rate(&self) -> u64 {
+        self.0.reward_rate
+    }
+
+    #[wasm_bindgen(getter, js_name = cumulativeRewards)]
+    pub fn cumulative_rewards(&self) -> u64 {
+        self.0.cumulative_rewards
+    }
+
+    #[wasm_bindgen(getter, js_name = accTokensPerHashpower)]
+    pub fn acc_tokens_per_hashpower(&self) -> String {
+        self.0.acc_tokens_per_hashpower.to_string()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Tier {
+    total_cards: u8,
+    berry_capacity: u64,
+    upgrade_cost: u64,
+}
+
+/// The farm tier table, indexed by farm_type, decoded from the on-chain
+/// FarmConfig account. The authority can edit tiers at any time, so fetch the
+/// account rather than relying on the table it was seeded with.
+#[wasm_bindgen]
+pub struct FarmTiers(Vec<Tier>);
+
+#[wasm_bindgen]
+impl FarmTiers {
+    /// Decodes the raw data of the FarmConfig account (discriminator
+    /// included): a u8 tier count followed by (u8, u64, u64) tiers.
+    #[wasm_bindgen(js_name = fromAccountData)]
+    pub fn from_account_data(data: &[u8]) -> Result<FarmTiers, JsError> {
+        const TIER_SIZE: usize = 1 + 8 + 8;
+        let invalid = || JsError::new("FarmConfig account data is too short");
+
+        let tier_count = *data.get(8).ok_or_else(invalid)? as usize;
+        let tiers = data
+            .get(9..9 + tier_count * TIER_SIZE)
+            .ok_or_else(invalid)?
+            .chunks_exact(TIER_SIZE)
+            .map(|tier| Tier {
+                total_cards: tier[0],
+                berry_capacity: u64::from_le_bytes(tier[1..9].try_into().unwrap()),
+                upgrade_cost: u64::from_le_bytes(tier[9..17].try_into().unwrap()),
+            })
+            .collect();
+        Ok(Self(tiers))
+    }
+
+    /// Number of farm types; valid farm_type values are `0..count`.
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u8 {
+        self.0.len() as u8
+    }
+
+    /// Card slots of a farm type, or undefined for an unknown type.
+    #[wasm_bindgen(js_name = totalCards)]
+    pub fn total_cards(&self, farm_type: u8) -> Option<u8> {
+        self.0.get(farm_type as usize).map(|tier| tier.total_cards)
+    }
+
+    #[wasm_bindgen(js_name = berryCapacity)]
+    pub fn berry_capacity(&self, farm_type: u8) -> Option<u64> {
+        self.0
+            .get(farm_type as usize)
+            .map(|tier| tier.berry_capacity)
+    }
+
+    /// Token cost of upgrading *to* this farm type.
+    #[wasm_bindgen(js_name = upgradeCost)]
+    pub fn upgrade_cost(&self, farm_type: u8) -> Option<u64> {
+        self.0.get(farm_type as usize).map(|tier| tier.upgrade_cost)
+    }
+
+    /// Why stake_card would reject a card, mirroring the program's errors:
+    /// "MachineCapacityExceeded" (no free slot), "PowerCapacityExceeded"
+    /// (berry capacity), or undefined when the card can be staked.
+    #[wasm_bindgen(js_name = stakeRejection)]
+    pub fn stake_rejection(
+        &self,
+        staked_cards: u8,
+        farm_type: u8,
+        berries: u64,
+        card_berry_consumption: u64,
+    ) -> Option<String> {
+        let tier = self.0.get(farm_type as usize)?;
+        ponzimon_math::check_stake(
+            staked_cards,
+            tier.total_cards,
+            berries,
+            tier.berry_capacity,
+            card_berry_consumption,
+        )
+        .err()
+        .map(|e| {
+            match e {
+                StakeError::NoFreeSlot => "MachineCapacityExceeded",
+                StakeError::BerryCapacityExceeded => "PowerCapacityExceeded",
+            }
+            .to_string()
+        })
+    }
+}
+
+#[wasm_bindgen(js_name = maxCardsPerPlayer)]
+pub fn max_cards_per_player() -> u8 {
+    MAX_CARDS_PER_PLAYER
+}
+
+/// Booster slot odds in per-mille, indexed by rarity (COMMON = 0).
+#[wasm_bindgen(js_name = boosterRarityPerMille)]
+pub fn booster_rarity_per_mille() -> Vec<u32> {
+    BOOSTER_RARITY_PER_MILLE.to_vec()
+}
+
+/// The rarity a booster slot rolls from a random u32, as settle_open_booster does.
+#[wasm_bindgen(js_name = rollBoosterRarity)]
+pub fn roll_booster_rarity(random_u32: u32) -> u8 {
+    ponzimon_math::roll_booster_rarity(random_u32)
+}
+
+/// Chance in basis points that recycling a card of `rarity` upgrades it,
+/// given GlobalState.recycle_upgrade_odds_bps.
+#[wasm_bindgen(js_name = recycleUpgradeOddsBps)]
+pub fn recycle_upgrade_odds_bps(upgrade_odds_bps: &[u16], rarity: u8) -> u64 {
+    ponzimon_math::upgrade_odds_bps(upgrade_odds_bps, rarity)
+}
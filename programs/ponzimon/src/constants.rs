@@ -0,0 +1,48 @@
+//! Seeds, limits and the compiled game tables. The shared game constants live
+//! in ponzimon_math so off-chain tools get them without this crate.
+
+pub use ponzimon_math::constants::*;
+
+pub const GLOBAL_STATE_SEED: &[u8] = b"global_state";
+pub const PLAYER_SEED: &[u8] = b"player";
+pub const REWARDS_VAULT_SEED: &[u8] = b"rewards_vault";
+
+/// Slots between a commit and the SlotHashes entry its settle reveals.
+pub const MIN_RANDOMNESS_DELAY_SLOTS: u64 = 5;
+/// SlotHashes only keeps the last 512 slots; once the reveal slot has left it
+/// the action can never settle, so the player may cancel it instead.
+pub const CANCEL_TIMEOUT_SLOTS: u64 = 512;
+
+/// Farm tiers as (total_cards, berry_capacity, upgrade_cost), indexed by
+/// farm_type. Only seeds FarmConfig in initialize_farm_config; the live table
+/// is the on-chain account.
+pub const FARM_CONFIGS: [(u8, u64, u64); 11] = [
+    (2, 6, 0),
+    (2, 6, 0),
+    (4, 12, 1_000_000_000),
+    (7, 21, 2_500_000_000),
+    (10, 30, 5_000_000_000),
+    (13, 39, 10_000_000_000),
+    (16, 48, 20_000_000_000),
+    (19, 57, 40_000_000_000),
+    (22, 66, 80_000_000_000),
+    (25, 75, 160_000_000_000),
+    (25, 100, 320_000_000_000),
+];
+
+/// Cards as (id, rarity, hashpower, berry_consumption). Only seeds the
+/// CardCatalog in initialize_card_catalog; draws read the on-chain account.
+pub const CARD_DATA: [(u16, u8, u16, u8); 9] = [
+    (1, COMMON, 100, 2),
+    (2, COMMON, 120, 3),
+    (3, COMMON, 90, 2),
+    (10, UNCOMMON, 200, 3),
+    (20, RARE, 350, 4),
+    (30, DOUBLE_RARE, 600, 5),
+    (40, VERY_RARE, 1000, 6),
+    (50, SUPER_RARE, 1800, 8),
+    (60, MEGA_RARE, 3500, 10),
+];
+
+/// Cards every new farm starts with, unstaked.
+pub const STARTER_CARD_IDS: [u16; 3] = [1, 2, 3];
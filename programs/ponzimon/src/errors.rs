@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum PonzimonError {
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Production is disabled")]
+    ProductionDisabled,
+    #[msg("Initial farm already purchased")]
+    InitialFarmAlreadyPurchased,
+    #[msg("Self-referral is not allowed")]
+    SelfReferralNotAllowed,
+    #[msg("Referrer account missing")]
+    ReferrerAccountMissing,
+    #[msg("Invalid farm type")]
+    InvalidFarmType,
+    #[msg("Insufficient tokens")]
+    InsufficientTokens,
+    #[msg("Farm has no free card slot")]
+    MachineCapacityExceeded,
+    #[msg("Farm berry capacity exceeded")]
+    PowerCapacityExceeded,
+    #[msg("Card is staked")]
+    CardIsStaked,
+    #[msg("Card is not staked")]
+    CardNotStaked,
+    #[msg("Cooldown not expired")]
+    CooldownNotExpired,
+    #[msg("A booster is already pending")]
+    BoosterAlreadyPending,
+    #[msg("No booster pending")]
+    NoBoosterPending,
+    #[msg("A recycle is already pending")]
+    RecycleAlreadyPending,
+    #[msg("No recycle pending")]
+    NoRecyclePending,
+    #[msg("No pending action")]
+    NoPendingAction,
+    #[msg("Randomness not resolved yet")]
+    RandomnessNotResolved,
+    #[msg("Cancel timeout not expired")]
+    CancelTimeoutNotExpired,
+    #[msg("Invalid SlotHashes account")]
+    InvalidSlotHashes,
+    #[msg("Reveal slot not found in SlotHashes")]
+    SlotNotFound,
+    #[msg("Invalid recycle card count")]
+    InvalidRecycleCardCount,
+    #[msg("Duplicate card indices")]
+    DuplicateRecycleCardIndices,
+    #[msg("Invalid referral fee")]
+    InvalidReferralFee,
+    #[msg("Invalid burn rate")]
+    InvalidBurnRate,
+    #[msg("Invalid dust threshold divisor")]
+    InvalidDustThresholdDivisor,
+    #[msg("Invalid parameter index")]
+    InvalidParameterIndex,
+    #[msg("Invalid token mint")]
+    InvalidTokenMint,
+    #[msg("Invalid mint authority")]
+    InvalidMintAuthority,
+    #[msg("Invalid token account owner")]
+    InvalidTokenAccountOwner,
+    #[msg("Card index out of bounds")]
+    InvalidCardIndex,
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+    #[msg("Invalid pity rarity")]
+    InvalidPityRarity,
+    #[msg("Invalid pity threshold")]
+    InvalidPityThreshold,
+    #[msg("Card catalog is full")]
+    CatalogFull,
+    #[msg("Card id already in the catalog")]
+    DuplicateCatalogCard,
+    #[msg("Invalid card rarity")]
+    InvalidCardRarity,
+    #[msg("Card not in the catalog")]
+    CardNotInCatalog,
+    #[msg("Last active card of its rarity can't be retired")]
+    LastActiveCardOfRarity,
+    #[msg("Invalid recycle upgrade odds")]
+    InvalidRecycleOdds,
+    #[msg("Fusion needs a rarity that can be upgraded")]
+    InvalidFusionRarity,
+    #[msg("Fused cards must share one rarity")]
+    FusionRarityMismatch,
+    #[msg("Card not found")]
+    CardNotFound,
+    #[msg("Invalid player account")]
+    InvalidPlayerAccount,
+    #[msg("Player account already migrated")]
+    AlreadyMigrated,
+    #[msg("Card page is full")]
+    CardPageFull,
+    #[msg("A random action is pending")]
+    PendingActionInProgress,
+    #[msg("Invalid action batch")]
+    InvalidActionBatch,
+    #[msg("Card is locked")]
+    CardLocked,
+    #[msg("Insufficient shards")]
+    InsufficientShards,
+    #[msg("Crafting is disabled for this target")]
+    CraftingDisabled,
+    #[msg("Invalid craft target")]
+    InvalidCraftTarget,
+    #[msg("Invalid shard amount")]
+    InvalidShardAmount,
+    #[msg("Buyback unavailable")]
+    BuybackUnavailable,
+    #[msg("Insufficient buyback funds")]
+    InsufficientBuybackFunds,
+    #[msg("Invalid buyback share")]
+    InvalidBuybackShare,
+    #[msg("Invalid farm config")]
+    InvalidFarmConfig,
+    #[msg("Farm upgrade cooldown active")]
+    UpgradeCooldownActive,
+    #[msg("Claim cooldown active")]
+    ClaimCooldownActive,
+    #[msg("Invalid cooldown")]
+    InvalidCooldown,
+    #[msg("No referral rewards to claim")]
+    NoReferralRewards,
+    #[msg("Card serials not migrated yet")]
+    CardSerialsNotMigrated,
+}
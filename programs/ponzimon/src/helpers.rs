@@ -0,0 +1,43 @@
+use crate::{constants::*, errors::PonzimonError};
+use anchor_lang::prelude::*;
+
+pub fn safe_add_berries(current: u64, amount: u64) -> Result<u64> {
+    current
+        .checked_add(amount)
+        .ok_or_else(|| PonzimonError::ArithmeticOverflow.into())
+}
+
+pub fn safe_sub_berries(current: u64, amount: u64) -> Result<u64> {
+    current
+        .checked_sub(amount)
+        .ok_or_else(|| PonzimonError::ArithmeticOverflow.into())
+}
+
+pub fn safe_add_hashpower(current: u64, amount: u64) -> Result<u64> {
+    current
+        .checked_add(amount)
+        .ok_or_else(|| PonzimonError::ArithmeticOverflow.into())
+}
+
+pub fn safe_sub_hashpower(current: u64, amount: u64) -> Result<u64> {
+    current
+        .checked_sub(amount)
+        .ok_or_else(|| PonzimonError::ArithmeticOverflow.into())
+}
+
+pub fn validate_card_index(card_index: u8, card_count: usize) -> Result<()> {
+    require!(
+        (card_index as usize) < card_count,
+        PonzimonError::InvalidCardIndex
+    );
+    Ok(())
+}
+
+/// The rarity a recycle or fusion of `rarity` upgrades into; None for MEGA_RARE.
+pub fn get_next_rarity(rarity: u8) -> Option<u8> {
+    if rarity < MEGA_RARE {
+        Some(rarity + 1)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,4746 @@
+use crate::{constants::*, errors::PonzimonError, helpers::*, state::*};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::set_return_data;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_lang::Discriminator;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer},
+};
+use ponzimon_math::{
+    check_stake, optimal_loadout, pool::RewardPool, split_burn, split_buyback, split_referral,
+    LoadoutCard, LoadoutLimits, StakeError,
+};
+
+#[event]
+pub struct FarmUpgraded {
+    pub player: Pubkey,
+    pub new_farm_type: u8,
+}
+
+#[event]
+pub struct CardStaked {
+    pub player: Pubkey,
+    pub card_serial: u64,
+}
+
+#[event]
+pub struct CardUnstaked {
+    pub player: Pubkey,
+    pub card_serial: u64,
+}
+
+#[event]
+pub struct CardDiscarded {
+    pub player: Pubkey,
+    pub card_serial: u64,
+    pub shards_earned: u64,
+}
+
+#[event]
+pub struct BoosterOpened {
+    pub player: Pubkey,
+    // Events have a size limit, so we can't log the full card details.
+    // We'll log the card types as a simple array.
+    pub card_types: [u8; 5],
+    pub pity_triggered: bool, // True if one slot was forced up to the pity rarity
+}
+
+/// Number of rarity transitions that can be rolled by recycling
+/// (COMMON→UNCOMMON … SUPER_RARE→MEGA_RARE); indexed by source rarity.
+pub const RECYCLE_ODDS_LEN: usize = 6;
+/// Number of card rarities (COMMON … MEGA_RARE); sizes the per-rarity shard tables.
+pub const RARITY_COUNT: usize = RECYCLE_ODDS_LEN + 1;
+pub use ponzimon_math::BPS_DENOMINATOR;
+
+#[event]
+pub struct CardsRecycled {
+    pub player: Pubkey,
+    pub successful_upgrades: u8, // Number of cards that were successfully upgraded
+    pub total_recycled: u8,      // Total number of cards that were recycled
+    pub upgrade_odds_bps: [u16; RECYCLE_ODDS_LEN], // Odds table used for this settlement
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+/// INTERNAL: card serials
+/// ────────────────────────────────────────────────────────────────────────────
+/// Serialized size of a Card: id (2) + rarity (1) + hashpower (2) + berry_consumption (1) + serial (8)
+pub const CARD_SIZE: usize = 14;
+/// Card size before serials were introduced
+pub const LEGACY_CARD_SIZE: usize = 6;
+
+/// Stamps a freshly drawn card with the next global serial. Serial 0 is never
+/// issued so it can mark legacy, not yet migrated cards.
+fn mint_card(gs: &mut GlobalState, card: Card) -> Card {
+    let serial = gs.next_card_serial.max(1);
+    gs.next_card_serial = serial + 1;
+    Card { serial, ..card }
+}
+
+/// Resolves a card serial to its current position in `player.cards`.
+fn find_card_by_serial(player: &Player, card_serial: u64) -> Result<u8> {
+    require!(card_serial != 0, PonzimonError::CardNotFound);
+    player.cards[..player.card_count as usize]
+        .iter()
+        .position(|card| card.serial == card_serial)
+        .map(|index| index as u8)
+        .ok_or_else(|| PonzimonError::CardNotFound.into())
+}
+
+/// Removes the cards at `indices` outside of a recycle or fusion settlement.
+/// Every removal shifts the cards after it down, which would leave a pending
+/// Recycle/Fusion pointing at other cards, so nothing may be pending.
+fn remove_cards(player: &mut Player, indices: &[u8]) -> Result<()> {
+    require!(
+        player.pending_action() == PendingRandomAction::None,
+        PonzimonError::PendingActionInProgress
+    );
+    player.batch_remove_cards(indices)
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+/// INTERNAL: update the global accumulator
+/// ────────────────────────────────────────────────────────────────────────────
+fn reward_pool(gs: &GlobalState) -> RewardPool {
+    RewardPool {
+        start_slot: gs.start_slot,
+        last_reward_slot: gs.last_reward_slot,
+        reward_rate: gs.reward_rate,
+        total_hashpower: gs.total_hashpower,
+        total_supply: gs.total_supply,
+        cumulative_rewards: gs.cumulative_rewards,
+        burned_tokens: gs.burned_tokens,
+        dust_threshold_divisor: gs.dust_threshold_divisor,
+        acc_tokens_per_hashpower: gs.acc_tokens_per_hashpower,
+    }
+}
+
+/// Advances the global accumulator to `slot_now`; the formula itself lives in
+/// ponzimon_math so the simulator and frontend use the exact same code.
+fn update_pool(gs: &mut GlobalState, slot_now: u64) {
+    let mut pool = reward_pool(gs);
+    ponzimon_math::update_pool(&mut pool, slot_now, ACC_SCALE);
+
+    gs.last_reward_slot = pool.last_reward_slot;
+    gs.reward_rate = pool.reward_rate;
+    gs.cumulative_rewards = pool.cumulative_rewards;
+    gs.acc_tokens_per_hashpower = pool.acc_tokens_per_hashpower;
+}
+
+fn remaining_supply(gs: &GlobalState) -> u64 {
+    ponzimon_math::remaining_supply(gs.total_supply, gs.cumulative_rewards, gs.burned_tokens)
+}
+
+/// Rewards accrued by `player` since their last settlement, clamped to the
+/// remaining supply. Expects `gs` to have been brought up to date by update_pool.
+fn pending_rewards(player: &Player, gs: &GlobalState) -> u64 {
+    ponzimon_math::pending_rewards(
+        player.total_hashpower,
+        gs.acc_tokens_per_hashpower,
+        player.last_acc_tokens_per_hashpower,
+        remaining_supply(gs),
+        ACC_SCALE,
+    )
+}
+
+/// First slot at which settlement pays out again after the last payout.
+fn claim_ready_slot(player: &Player, gs: &GlobalState) -> u64 {
+    player
+        .last_claim_slot
+        .saturating_add(gs.claim_cooldown_slots as u64)
+}
+
+/// Fails with ClaimCooldownActive, logging the slots left, before `ready_slot`
+/// (see claim_ready_slot). Before production starts there is nothing to claim
+/// yet, so nothing to hold back either.
+fn check_claim_cooldown(ready_slot: u64, gs: &GlobalState, slot: u64) -> Result<()> {
+    if slot >= gs.start_slot && slot < ready_slot {
+        msg!(
+            "Claim cooldown active: {} slots remaining",
+            ready_slot - slot
+        );
+        return err!(PonzimonError::ClaimCooldownActive);
+    }
+    Ok(())
+}
+
+/// Fails with UpgradeCooldownActive, logging the slots left, while the farm
+/// upgrade cooldown since the last upgrade (or the initial purchase) runs.
+fn check_upgrade_cooldown(player: &Player, gs: &GlobalState, slot: u64) -> Result<()> {
+    let ready_slot = player
+        .last_upgrade_slot
+        .saturating_add(gs.upgrade_cooldown_slots as u64);
+    if slot < ready_slot {
+        msg!(
+            "Farm upgrade cooldown active: {} slots remaining",
+            ready_slot - slot
+        );
+        return err!(PonzimonError::UpgradeCooldownActive);
+    }
+    Ok(())
+}
+
+/// Helper to settle and mint rewards for a player.
+/// Returns Ok(amount_claimed) or Ok(0) if nothing to claim.
+#[allow(clippy::too_many_arguments)]
+fn settle_and_mint_rewards<'info>(
+    player: &mut Player,
+    gs: &mut Account<'info, GlobalState>,
+    now: u64,
+    player_token_account: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
+    rewards_vault: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    global_state_bump: u8,
+) -> Result<u64> {
+    // update pool to now
+    update_pool(gs, now);
+
+    if now < gs.start_slot {
+        player.last_claim_slot = now;
+        return Ok(0);
+    }
+
+    require!(
+        now > player.last_claim_slot,
+        PonzimonError::CooldownNotExpired
+    );
+
+    // calculate pending
+    let pending = pending_rewards(player, gs);
+
+    // Claim cooldown: inside the window rewards are still settled (hashpower may
+    // change right after this), but held on the player and paid out with the
+    // first settlement once the window has passed.
+    if now < claim_ready_slot(player, gs) {
+        player.accrued_rewards = player.accrued_rewards.saturating_add(pending);
+        player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+        return Ok(0);
+    }
+    let pending = pending.saturating_add(player.accrued_rewards);
+    player.accrued_rewards = 0;
+
+    if pending == 0 {
+        player.last_claim_slot = now;
+        player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+        return Ok(0);
+    }
+
+    // update player bookkeeping
+    player.last_claim_slot = now;
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+
+    // Give the player their full rewards - no deduction for referrals
+    let player_amount = pending;
+
+    // Update player total rewards (Effect)
+    player.total_rewards = player.total_rewards.saturating_add(player_amount);
+
+    // signer seeds
+    let token_mint_key = &token_mint.key();
+    let seeds = &[
+        GLOBAL_STATE_SEED,
+        token_mint_key.as_ref(),
+        &[global_state_bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    // mint to player - they get their full rewards
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            Transfer {
+                from: rewards_vault.clone(),
+                to: player_token_account.clone(),
+                authority: gs.to_account_info(),
+            },
+            signer,
+        ),
+        player_amount,
+    )?;
+
+    Ok(pending)
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+/* ──────────────────────────
+INITIALIZE
+────────────────────────── */
+#[derive(Accounts)]
+pub struct InitializeProgram<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        init,
+        payer = authority,
+        space = GlobalState::SIZE,
+        seeds=[GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// CHECK: This is the fees recipient wallet
+    pub fees_wallet: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = token_mint,
+        associated_token::authority = fees_wallet,
+    )]
+    pub fees_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = global_state,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = token_mint.mint_authority.unwrap() == global_state.key() @ PonzimonError::InvalidMintAuthority
+    )]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_program(
+    ctx: Context<InitializeProgram>,
+    start_slot: u64,
+    total_supply: u64,
+    reward_rate: u64,
+    initial_farm_purchase_fee_lamports: Option<u64>,
+    booster_pack_cost_microtokens: Option<u64>,
+    gamble_fee_lamports: Option<u64>,
+    staking_lockup_slots: u64,
+    token_reward_rate: u64,
+) -> Result<()> {
+    let gs = &mut ctx.accounts.global_state;
+
+    gs.authority = ctx.accounts.authority.key();
+    gs.token_mint = ctx.accounts.token_mint.key();
+    gs.fees_wallet = ctx.accounts.fees_wallet.key();
+    gs.rewards_vault = ctx.accounts.rewards_vault.key();
+
+    gs.total_supply = total_supply;
+    gs.burned_tokens = 0;
+    gs.cumulative_rewards = 0;
+
+    gs.start_slot = start_slot;
+    gs.reward_rate = reward_rate;
+
+    gs.acc_tokens_per_hashpower = 0;
+    gs.last_reward_slot = start_slot;
+
+    gs.burn_rate = 80;
+    gs.referral_fee = 100;
+    gs.referral_fee_initial_farm = 0;
+    gs.referral_fee_upgrade = 0;
+    gs.production_enabled = true;
+    gs.dust_threshold_divisor = 1000; // Default to 0.1%
+
+    // Initialize fee configuration with defaults from constants
+    gs.initial_farm_purchase_fee_lamports =
+        initial_farm_purchase_fee_lamports.unwrap_or(300_000_000); // 0.3 SOL
+    gs.booster_pack_cost_microtokens = booster_pack_cost_microtokens.unwrap_or(100_000_000); // 10 tokens
+    gs.gamble_fee_lamports = gamble_fee_lamports.unwrap_or(100_000_000); // 0.1 SOL
+
+    gs.total_berries = 0;
+    gs.total_hashpower = 0;
+
+    // Initialize new tracking fields
+    gs.total_booster_packs_opened = 0;
+    gs.total_card_recycling_attempts = 0;
+    gs.total_successful_card_recycling = 0;
+
+    // Staking pool
+    gs.total_staked_tokens = 0;
+    gs.staking_lockup_slots = staking_lockup_slots;
+    gs.acc_sol_rewards_per_token = 0;
+    gs.acc_token_rewards_per_token = 0;
+    gs.last_staking_reward_slot = start_slot;
+    gs.token_reward_rate = token_reward_rate;
+    gs.total_sol_deposited = 0;
+
+    // Pity: guarantee a RARE or better after 10 boosters without one
+    gs.pity_rarity = RARE;
+    gs.pity_threshold = 10;
+
+    // Recycle upgrade odds per source rarity, 20% across the board
+    gs.recycle_upgrade_odds_bps = [ponzimon_math::DEFAULT_UPGRADE_ODDS_BPS; RECYCLE_ODDS_LEN];
+    gs.recycle_odds_initialized = true;
+
+    // Card serials start at 1; 0 marks cards minted before serials existed
+    gs.next_card_serial = 1;
+
+    // Shards: discarding yields a rarity's value, crafting a card of that
+    // rarity costs five times as much
+    gs.discard_shards = [1, 2, 5, 10, 25, 60, 150];
+    gs.craft_booster_shards = 25;
+    gs.craft_card_shards = gs.discard_shards.map(|shards| shards * 5);
+
+    // Cooldowns are off until the authority sets them
+    gs.upgrade_cooldown_slots = 0;
+    gs.claim_cooldown_slots = 0;
+
+    // Mint initial supply to rewards vault
+    let preminted_supply = ctx.accounts.token_mint.supply;
+    let amount_to_mint = total_supply.saturating_sub(preminted_supply);
+
+    if amount_to_mint > 0 {
+        let token_mint_key = ctx.accounts.token_mint.key();
+        let seeds = &[
+            GLOBAL_STATE_SEED,
+            token_mint_key.as_ref(),
+            &[ctx.bumps.global_state],
+        ];
+        let signer = &[&seeds[..]];
+
+        token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.rewards_vault.to_account_info(),
+                    authority: gs.to_account_info(),
+                },
+                signer,
+            ),
+            amount_to_mint,
+        )?;
+    }
+
+    // Set new mint authority to none
+    let token_mint_key = ctx.accounts.token_mint.key();
+    let seeds = &[
+        GLOBAL_STATE_SEED,
+        token_mint_key.as_ref(),
+        &[ctx.bumps.global_state],
+    ];
+    let signer = &[&seeds[..]];
+
+    token::set_authority(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            token::SetAuthority {
+                current_authority: gs.to_account_info(),
+                account_or_mint: ctx.accounts.token_mint.to_account_info(),
+            },
+            signer,
+        ),
+        token::spl_token::instruction::AuthorityType::MintTokens,
+        None,
+    )?;
+    // Already disabled freeze authority
+    // token::set_authority(
+    //     CpiContext::new_with_signer(
+    //         ctx.accounts.token_program.to_account_info(),
+    //         token::SetAuthority {
+    //             current_authority: gs.to_account_info(),
+    //             account_or_mint: ctx.accounts.token_mint.to_account_info(),
+    //         },
+    //         signer,
+    //     ),
+    //     token::spl_token::instruction::AuthorityType::FreezeAccount,
+    //     None,
+    // )?;
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  PURCHASE INITIAL FARM
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+pub struct PurchaseInitialFarm<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        init,
+        payer = player_wallet,
+        // discriminator + zero-copy Player. The Pod layout is fixed by repr(C, packed) in
+        // state.rs, so the size is taken from the type rather than summed by hand.
+        space = 8 + std::mem::size_of::<Player>(),
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+
+    /// CHECK: This is the fees recipient wallet from global_state
+    #[account(
+        mut,
+        constraint = fees_wallet.key() == global_state.fees_wallet @ PonzimonError::Unauthorized
+    )]
+    pub fees_wallet: AccountInfo<'info>,
+    /// CHECK: This is the referrer's wallet. Optional. If provided, the wallet key is used as the referrer.
+    #[account(mut)]
+    pub referrer_wallet: Option<AccountInfo<'info>>,
+    #[account(
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    #[account(
+        mut,
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = player_wallet,
+        associated_token::mint = token_mint,
+        associated_token::authority = player_wallet,
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [FARM_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub farm_config: Box<Account<'info, FarmConfig>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[event]
+pub struct InitialFarmPurchased {
+    pub player_wallet: Pubkey,
+    pub player_account: Pubkey,
+    pub referrer: Option<Pubkey>,
+    pub referral_commission_lamports: u64,
+    pub farm_type: u8,
+    pub initial_cards: u8,
+    pub initial_hashpower: u64,
+    pub slot: u64,
+}
+
+pub fn purchase_initial_farm(ctx: Context<PurchaseInitialFarm>) -> Result<()> {
+    // for now allow only certain addresses to purchase initial farm
+    let allowed_addresses = [
+        "92mEoL7Yh8iKLHNTt1q5fWSY1q2NE1hPXnicn8FwE1J1",
+        "7zyvsoxTHHrEYxMYyYxyajhjJgHcfyeVHASDBD7ys8JJ",
+        "26jWitfbhcoSekDwQVffowob6Qe4cHZRgxEgEN66xqE7",
+        "4RFW19mwhpfYD82Q1rpPYwe9Tr2MSBJUW81mMrUd8v8W",
+        "6bShXs6Lpi47mxZ6u6MRabfoKVh2i2E4xBemuhyc58U4",
+    ];
+    #[cfg(not(feature = "devnet"))]
+    #[cfg(not(feature = "test"))]
+    require!(
+        allowed_addresses.contains(&ctx.accounts.player_wallet.key().to_string().as_str()),
+        PonzimonError::Unauthorized
+    );
+
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_init()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    require!(
+        player.card_count == 0,
+        PonzimonError::InitialFarmAlreadyPurchased
+    );
+
+    // The referrer is now derived from the optional `referrer_wallet` account.
+    let referrer: Option<Pubkey> = ctx.accounts.referrer_wallet.as_ref().map(|acc| acc.key());
+
+    // Prevent self-referral.
+    if let Some(ref r) = referrer {
+        require!(
+            *r != ctx.accounts.player_wallet.key(),
+            PonzimonError::SelfReferralNotAllowed
+        );
+    }
+
+    // Make sure the reward pool is up to date before any state changes.
+    update_pool(gs, slot);
+
+    // --- Fee and Referral Logic ---
+    let mut referral_commission_lamports = 0;
+    let mut protocol_fee_lamports = gs.initial_farm_purchase_fee_lamports;
+    if let Some(referrer_wallet) = &ctx.accounts.referrer_wallet {
+        (referral_commission_lamports, protocol_fee_lamports) = split_referral(
+            gs.initial_farm_purchase_fee_lamports,
+            gs.referral_fee_initial_farm,
+        );
+        // A transfer that leaves an unfunded referrer below rent exemption
+        // would fail the whole purchase; the protocol keeps such a commission.
+        let referrer_balance = referrer_wallet
+            .lamports()
+            .saturating_add(referral_commission_lamports);
+        if referral_commission_lamports > 0
+            && referrer_balance < ctx.accounts.rent.minimum_balance(0)
+        {
+            protocol_fee_lamports =
+                protocol_fee_lamports.saturating_add(referral_commission_lamports);
+            referral_commission_lamports = 0;
+        }
+        if referral_commission_lamports > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.player_wallet.to_account_info(),
+                        to: referrer_wallet.to_account_info(),
+                    },
+                ),
+                referral_commission_lamports,
+            )?;
+        }
+    }
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.player_wallet.to_account_info(),
+                to: ctx.accounts.fees_wallet.to_account_info(),
+            },
+        ),
+        protocol_fee_lamports,
+    )?;
+
+    // player bootstrap
+    player.owner = ctx.accounts.player_wallet.key();
+    let tier = ctx.accounts.farm_config.tier(1)?;
+    player.farm = Farm {
+        farm_type: 1,
+        total_cards: tier.total_cards,
+        berry_capacity: tier.berry_capacity,
+    };
+
+    // Initialize arrays
+    player.cards = [Card::default(); MAX_CARDS_PER_PLAYER as usize];
+    player.card_count = 0;
+    player.staked_cards_bitset = 0; // No cards staked initially
+
+    // Give player 3 starter cards using the IDs from data.ts (not staked initially).
+    // Card stats are read from the on-chain catalog so rebalances apply to new players.
+    let catalog = ctx.accounts.card_catalog.load()?;
+    for &card_id in STARTER_CARD_IDS.iter() {
+        if let Some(entry) = catalog.get(card_id) {
+            player.add_card(mint_card(gs, entry.to_card()))?;
+        }
+    }
+
+    player.berries = 0; // No cards staked initially
+    player.total_hashpower = 0; // No cards staked initially
+    player.set_referrer(referrer);
+    player.last_claim_slot = slot;
+    player.last_upgrade_slot = slot;
+    player.total_rewards = 0;
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+
+    // Initialize gambling fields
+    player.total_gambles = 0;
+    player.total_gamble_wins = 0;
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+
+    // Initialize new tracking fields
+    player.total_earnings_for_referrer = 0;
+    player.total_sol_earned_for_referrer = referral_commission_lamports;
+    player.total_booster_packs_opened = 0;
+    player.total_cards_recycled = 0;
+    player.successful_card_recycling = 0;
+    player.total_sol_spent = gs.initial_farm_purchase_fee_lamports;
+    player.total_tokens_spent = 0;
+
+    // Initialize staking stats
+    player.staked_tokens = 0;
+    player.last_stake_slot = 0;
+    player.last_acc_sol_rewards_per_token = 0;
+    player.last_acc_token_rewards_per_token = 0;
+    player.claimed_token_rewards = 0;
+
+    // Initialize pity counter
+    player.boosters_without_pity = 0;
+
+    // Initialize padding field
+    player.padding.fill(0);
+
+    // global stats (Effect) - no initial berry consumption since cards aren't staked
+    // gs.total_berries += 0; // No change needed
+
+    emit!(InitialFarmPurchased {
+        player_wallet: ctx.accounts.player_wallet.key(),
+        player_account: player_key,
+        referrer,
+        referral_commission_lamports,
+        farm_type: player.farm.farm_type,
+        initial_cards: player.card_count,
+        initial_hashpower: player.berries, // 0 since no cards are staked initially
+        slot,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  DISCARD CARD
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+#[instruction(card_serial: u64)]
+pub struct DiscardCard<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::PendingActionInProgress,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = fees_token_account.mint == global_state.token_mint,
+        constraint = fees_token_account.owner == global_state.fees_wallet @ PonzimonError::Unauthorized
+    )]
+    pub fees_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Validates and removes one unstaked card. Rewards must already be settled.
+/// Shared by discard_card and execute_actions.
+fn apply_discard(
+    player: &mut Player,
+    gs: &GlobalState,
+    player_key: Pubkey,
+    card_serial: u64,
+) -> Result<()> {
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+
+    let card_index = find_card_by_serial(player, card_serial)?;
+
+    // Security: Validate card index bounds
+    validate_card_index(card_index, player.card_count as usize)?;
+
+    // Ensure the card is not currently staked
+    require!(
+        !player.is_card_staked(card_index),
+        PonzimonError::CardIsStaked
+    );
+
+    // Ensure the card is not currently being recycled
+    require!(
+        !player.is_card_being_recycled(card_index),
+        PonzimonError::CardIsStaked // Reusing this error for consistency
+    );
+
+    require!(
+        !player.is_card_locked(card_index),
+        PonzimonError::CardLocked
+    );
+
+    // Salvage: the card is worth its rarity's shard value
+    let rarity = player.cards[card_index as usize].rarity as usize;
+    let shards_earned = gs.discard_shards.get(rarity).copied().unwrap_or(0) as u64;
+
+    // Remove the card using the helper function
+    remove_cards(player, &[card_index])?;
+
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+    player.shards = player.shards.saturating_add(shards_earned);
+
+    emit!(CardDiscarded {
+        player: player_key,
+        card_serial,
+        shards_earned,
+    });
+
+    Ok(())
+}
+
+pub fn discard_card(ctx: Context<DiscardCard>, card_serial: u64) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    apply_discard(&mut player, gs, player_key, card_serial)
+}
+
+#[event]
+pub struct CardsDiscarded {
+    pub player: Pubkey,
+    pub card_serials: Vec<u64>,
+    pub card_ids: Vec<u16>,
+    pub shards_earned: u64,
+}
+
+#[derive(Accounts)]
+pub struct DiscardCards<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::PendingActionInProgress,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Discards several unstaked cards with one settlement and one removal pass.
+pub fn discard_cards(ctx: Context<DiscardCards>, card_serials: Vec<u64>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    require!(
+        !card_serials.is_empty() && card_serials.len() <= MAX_CARDS_PER_PLAYER as usize,
+        PonzimonError::InvalidRecycleCardCount
+    );
+
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    let card_indices = card_serials
+        .iter()
+        .map(|&serial| find_card_by_serial(&player, serial))
+        .collect::<Result<Vec<u8>>>()?;
+
+    // Validate card indices: must be unique, valid, unstaked, unlocked and not pending
+    let mut sorted_indices = card_indices.clone();
+    sorted_indices.sort();
+    for i in 1..sorted_indices.len() {
+        require!(
+            sorted_indices[i] != sorted_indices[i - 1],
+            PonzimonError::DuplicateRecycleCardIndices
+        );
+    }
+    let mut card_ids = Vec::with_capacity(card_indices.len());
+    let mut shards_earned: u64 = 0;
+    for &index in &card_indices {
+        validate_card_index(index, player.card_count as usize)?;
+        require!(!player.is_card_staked(index), PonzimonError::CardIsStaked);
+        require!(
+            !player.is_card_being_recycled(index),
+            PonzimonError::CardIsStaked // Reusing this error for consistency
+        );
+        require!(!player.is_card_locked(index), PonzimonError::CardLocked);
+
+        let card = &player.cards[index as usize];
+        card_ids.push(card.id);
+        shards_earned = shards_earned.saturating_add(
+            gs.discard_shards
+                .get(card.rarity as usize)
+                .copied()
+                .unwrap_or(0) as u64,
+        );
+    }
+
+    remove_cards(&mut player, &card_indices)?;
+
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+    player.shards = player.shards.saturating_add(shards_earned);
+
+    emit!(CardsDiscarded {
+        player: player_key,
+        card_serials,
+        card_ids,
+        shards_earned,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  LOCK CARDS (protect cards from discard, recycle, fusion and page moves)
+/// ────────────────────────────────────────────────────────────────────────────
+#[event]
+pub struct CardsLockChanged {
+    pub player: Pubkey,
+    pub card_serials: Vec<u64>,
+    pub locked: bool,
+}
+
+#[derive(Accounts)]
+pub struct LockCards<'info> {
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+fn set_cards_locked(ctx: Context<LockCards>, card_serials: Vec<u64>, locked: bool) -> Result<()> {
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+
+    require!(
+        !card_serials.is_empty() && card_serials.len() <= MAX_CARDS_PER_PLAYER as usize,
+        PonzimonError::InvalidRecycleCardCount
+    );
+
+    for &serial in &card_serials {
+        let index = find_card_by_serial(&player, serial)?;
+        if locked {
+            // A card already committed to a recycle or fusion can't be saved anymore
+            require!(
+                !player.is_card_being_recycled(index),
+                PonzimonError::CardIsStaked // Reusing this error for consistency
+            );
+        }
+        player.set_card_locked(index, locked);
+    }
+
+    emit!(CardsLockChanged {
+        player: player_key,
+        card_serials,
+        locked,
+    });
+
+    Ok(())
+}
+
+pub fn lock_cards(ctx: Context<LockCards>, card_serials: Vec<u64>) -> Result<()> {
+    set_cards_locked(ctx, card_serials, true)
+}
+
+pub fn unlock_cards(ctx: Context<LockCards>, card_serials: Vec<u64>) -> Result<()> {
+    set_cards_locked(ctx, card_serials, false)
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  STAKE CARD
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+#[instruction(card_serial: u64)]
+pub struct StakeCard<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Validates and stakes one card, updating player and global totals. Rewards
+/// must already be settled. Shared by stake_card and execute_actions.
+fn apply_stake(
+    player: &mut Player,
+    gs: &mut GlobalState,
+    player_key: Pubkey,
+    card_serial: u64,
+) -> Result<()> {
+    let card_index = find_card_by_serial(player, card_serial)?;
+
+    // Security: Validate card index bounds
+    validate_card_index(card_index, player.card_count as usize)?;
+
+    require!(
+        !player.is_card_staked(card_index),
+        PonzimonError::CardIsStaked // Using for "already staked"
+    );
+
+    // Ensure the card is not currently being recycled
+    require!(
+        !player.is_card_being_recycled(card_index),
+        PonzimonError::CardIsStaked // Reusing this error for consistency
+    );
+
+    let card = &player.cards[card_index as usize];
+    let card_berry_consumption = card.berry_consumption as u64;
+    let card_hashpower = card.hashpower as u64;
+
+    check_stake(
+        player.count_staked_cards(),
+        player.farm.total_cards,
+        player.berries,
+        player.farm.berry_capacity,
+        card_berry_consumption,
+    )
+    .map_err(|e| match e {
+        StakeError::NoFreeSlot => PonzimonError::MachineCapacityExceeded,
+        StakeError::BerryCapacityExceeded => PonzimonError::PowerCapacityExceeded,
+    })?;
+
+    // Security: Use safe arithmetic for berry and power calculations
+    let new_player_berries = safe_add_berries(player.berries, card_berry_consumption)?;
+    let new_total_berries = safe_add_berries(gs.total_berries, card_berry_consumption)?;
+    let new_player_hashpower = safe_add_hashpower(player.total_hashpower, card_hashpower)?;
+    let new_total_hashpower = safe_add_hashpower(gs.total_hashpower, card_hashpower)?;
+
+    // Effects
+    player.stake_card(card_index)?;
+    player.berries = new_player_berries;
+    player.total_hashpower = new_player_hashpower;
+    gs.total_berries = new_total_berries;
+    gs.total_hashpower = new_total_hashpower;
+
+    emit!(CardStaked {
+        player: player_key,
+        card_serial,
+    });
+
+    Ok(())
+}
+
+pub fn stake_card(ctx: Context<StakeCard>, card_serial: u64) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    // Settle rewards before making changes
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    apply_stake(&mut player, gs, player_key, card_serial)
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  UNSTAKE CARD
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+#[instruction(card_serial: u64)]
+pub struct UnstakeCard<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Validates and unstakes one card, updating player and global totals.
+/// Rewards must already be settled. Shared by unstake_card and execute_actions.
+fn apply_unstake(
+    player: &mut Player,
+    gs: &mut GlobalState,
+    player_key: Pubkey,
+    card_serial: u64,
+) -> Result<()> {
+    let card_index = find_card_by_serial(player, card_serial)?;
+
+    // Security: Validate card index bounds
+    validate_card_index(card_index, player.card_count as usize)?;
+
+    require!(
+        player.is_card_staked(card_index),
+        PonzimonError::CardNotStaked
+    );
+
+    // Ensure the card is not currently being recycled
+    require!(
+        !player.is_card_being_recycled(card_index),
+        PonzimonError::CardIsStaked // Reusing this error for consistency
+    );
+
+    let card = &player.cards[card_index as usize];
+    let card_berry_consumption = card.berry_consumption as u64;
+    let card_hashpower = card.hashpower as u64;
+
+    // Security: Use safe arithmetic for berry and power calculations
+    let new_player_berries = safe_sub_berries(player.berries, card_berry_consumption)?;
+    let new_total_berries = safe_sub_berries(gs.total_berries, card_berry_consumption)?;
+    let new_player_hashpower = safe_sub_hashpower(player.total_hashpower, card_hashpower)?;
+    let new_total_hashpower = safe_sub_hashpower(gs.total_hashpower, card_hashpower)?;
+
+    // Effects
+    player.unstake_card(card_index)?;
+    player.berries = new_player_berries;
+    player.total_hashpower = new_player_hashpower;
+    gs.total_berries = new_total_berries;
+    gs.total_hashpower = new_total_hashpower;
+
+    emit!(CardUnstaked {
+        player: player_key,
+        card_serial,
+    });
+
+    Ok(())
+}
+
+pub fn unstake_card(ctx: Context<UnstakeCard>, card_serial: u64) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    // Settle rewards before making changes
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    apply_unstake(&mut player, gs, player_key, card_serial)
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  UPGRADE FARM
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+#[instruction(farm_type: u8)]
+pub struct UpgradeFarm<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = fees_token_account.mint == global_state.token_mint,
+        constraint = fees_token_account.owner == global_state.fees_wallet @ PonzimonError::Unauthorized
+    )]
+    pub fees_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Only its key is used, to derive referral_vault. Must be the player's recorded referrer.
+    #[account(
+        constraint = Some(referrer_wallet.key()) == player.load()?.referrer() @ PonzimonError::ReferrerAccountMissing
+    )]
+    pub referrer_wallet: Option<AccountInfo<'info>>,
+    /// Required when the player has a referrer and referral_fee_upgrade is non-zero
+    #[account(
+        init_if_needed,
+        payer = player_wallet,
+        token::mint = token_mint,
+        token::authority = global_state,
+        seeds = [REFERRAL_VAULT_SEED, token_mint.key().as_ref(), referrer_wallet.as_ref().ok_or(PonzimonError::ReferrerAccountMissing)?.key.as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [FARM_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub farm_config: Box<Account<'info, FarmConfig>>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Validates a one-tier farm upgrade against `token_balance` and applies it to
+/// the player. Returns the cost, which the caller routes through
+/// handle_fee_transfers. Shared by upgrade_farm and execute_actions.
+fn apply_farm_upgrade(
+    player: &mut Player,
+    gs: &GlobalState,
+    farm_config: &FarmConfig,
+    farm_type: u8,
+    token_balance: u64,
+    slot: u64,
+) -> Result<u64> {
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    require!(
+        player.farm.farm_type + 1 == farm_type,
+        PonzimonError::InvalidFarmType
+    );
+
+    let tier = farm_config.tier(farm_type)?;
+    let cost = tier.upgrade_cost;
+
+    require!(token_balance >= cost, PonzimonError::InsufficientTokens);
+
+    // === EFFECTS ===
+    // Update player farm and state
+    player.farm.farm_type = farm_type;
+    player.farm.total_cards = tier.total_cards;
+    player.farm.berry_capacity = tier.berry_capacity;
+    player.last_upgrade_slot = slot;
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+
+    // Update player spending tracking
+    player.total_tokens_spent = player.total_tokens_spent.saturating_add(cost);
+
+    Ok(cost)
+}
+
+pub fn upgrade_farm(ctx: Context<UpgradeFarm>, farm_type: u8) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    update_pool(gs, slot);
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    check_upgrade_cooldown(&player, gs, slot)?;
+
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    let cost = apply_farm_upgrade(
+        &mut player,
+        gs,
+        &ctx.accounts.farm_config,
+        farm_type,
+        ctx.accounts.player_token_account.amount,
+        slot,
+    )?;
+
+    // Handle burn and fee transfers with referral logic
+    let referral_fee = gs.referral_fee_upgrade;
+    handle_fee_transfers(
+        &mut player,
+        gs,
+        cost,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.fees_token_account.to_account_info(),
+        ctx.accounts
+            .referral_vault
+            .as_ref()
+            .map(|vault| vault.to_account_info()),
+        None,
+        &ctx.accounts.player_wallet.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        referral_fee,
+    )?;
+
+    emit!(FarmUpgraded {
+        player: ctx.accounts.player_wallet.key(),
+        new_farm_type: farm_type,
+    });
+
+    Ok(())
+}
+
+/// Upgrades through every tier up to `target_farm_type` in one go. The tier
+/// costs are summed and paid with a single burn and fee transfer; one
+/// FarmUpgraded event is emitted per tier crossed.
+pub fn upgrade_farm_to(ctx: Context<UpgradeFarm>, target_farm_type: u8) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+
+    // One cooldown per instruction, however many tiers it crosses
+    check_upgrade_cooldown(&player, gs, slot)?;
+
+    let current_farm_type = player.farm.farm_type;
+    let farm_config = &ctx.accounts.farm_config;
+    require!(
+        target_farm_type > current_farm_type && target_farm_type < farm_config.tier_count,
+        PonzimonError::InvalidFarmType
+    );
+
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+    // Settlement may have just paid out rewards, which count towards the cost
+    ctx.accounts.player_token_account.reload()?;
+
+    let total_cost = farm_config.active_tiers()
+        [current_farm_type as usize + 1..=target_farm_type as usize]
+        .iter()
+        .fold(0u64, |total, tier| total.saturating_add(tier.upgrade_cost));
+    require!(
+        ctx.accounts.player_token_account.amount >= total_cost,
+        PonzimonError::InsufficientTokens
+    );
+
+    let mut remaining_balance = ctx.accounts.player_token_account.amount;
+    for farm_type in current_farm_type + 1..=target_farm_type {
+        let cost = apply_farm_upgrade(
+            &mut player,
+            gs,
+            farm_config,
+            farm_type,
+            remaining_balance,
+            slot,
+        )?;
+        remaining_balance -= cost;
+    }
+
+    let referral_fee = gs.referral_fee_upgrade;
+    handle_fee_transfers(
+        &mut player,
+        gs,
+        total_cost,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.fees_token_account.to_account_info(),
+        ctx.accounts
+            .referral_vault
+            .as_ref()
+            .map(|vault| vault.to_account_info()),
+        None,
+        &ctx.accounts.player_wallet.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        referral_fee,
+    )?;
+
+    for farm_type in current_farm_type + 1..=target_farm_type {
+        emit!(FarmUpgraded {
+            player: ctx.accounts.player_wallet.key(),
+            new_farm_type: farm_type,
+        });
+    }
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  CLAIM REWARDS
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+pub struct ClaimRewards<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.owner == player_wallet.key(),
+        constraint = player_token_account.mint == global_state.token_mint
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+    let now = Clock::get()?.slot;
+    let mut player = ctx.accounts.player.load_mut()?;
+
+    // Other actions just defer payouts inside the cooldown; an explicit claim fails
+    let ready_slot = claim_ready_slot(&player, &ctx.accounts.global_state);
+    check_claim_cooldown(ready_slot, &ctx.accounts.global_state, now)?;
+
+    settle_and_mint_rewards(
+        &mut player,
+        &mut ctx.accounts.global_state,
+        now,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  EXECUTE ACTIONS (several player actions, one settlement, all-or-nothing)
+/// ────────────────────────────────────────────────────────────────────────────
+pub const MAX_ACTIONS_PER_BATCH: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerAction {
+    Stake {
+        card_serial: u64,
+    },
+    Unstake {
+        card_serial: u64,
+    },
+    Discard {
+        card_serial: u64,
+    },
+    UpgradeFarm {
+        farm_type: u8,
+    },
+    /// Rewards are settled once before any action runs, so an explicit claim
+    /// has nothing left to pay; like claim_rewards, it fails inside the claim
+    /// cooldown instead of deferring the payout.
+    Claim,
+}
+
+#[event]
+pub struct ActionsExecuted {
+    pub player: Pubkey,
+    pub action_count: u8,
+    pub rewards_claimed: u64,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteActions<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = fees_token_account.mint == global_state.token_mint,
+        constraint = fees_token_account.owner == global_state.fees_wallet @ PonzimonError::Unauthorized
+    )]
+    pub fees_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Only its key is used, to derive referral_vault. Must be the player's recorded referrer.
+    #[account(
+        constraint = Some(referrer_wallet.key()) == player.load()?.referrer() @ PonzimonError::ReferrerAccountMissing
+    )]
+    pub referrer_wallet: Option<AccountInfo<'info>>,
+    /// Required when the player has a referrer and referral_fee_upgrade is non-zero
+    #[account(
+        init_if_needed,
+        payer = player_wallet,
+        token::mint = token_mint,
+        token::authority = global_state,
+        seeds = [REFERRAL_VAULT_SEED, token_mint.key().as_ref(), referrer_wallet.as_ref().ok_or(PonzimonError::ReferrerAccountMissing)?.key.as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(
+        seeds = [FARM_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub farm_config: Box<Account<'info, FarmConfig>>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Runs a bot turn (claim, discard, stake, unstake, upgrade) in one
+/// instruction. Rewards are settled once up front; each action then goes
+/// through the same validation as its standalone instruction, in order, and
+/// any failure aborts the transaction so nothing is applied.
+pub fn execute_actions(ctx: Context<ExecuteActions>, actions: Vec<PlayerAction>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(
+        !actions.is_empty() && actions.len() <= MAX_ACTIONS_PER_BATCH,
+        PonzimonError::InvalidActionBatch
+    );
+
+    // Taken before settlement, which restarts the cooldown when it pays out
+    let claim_ready = claim_ready_slot(&player, gs);
+    let rewards_claimed = settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    for action in actions.iter() {
+        match *action {
+            PlayerAction::Stake { card_serial } => {
+                apply_stake(&mut player, gs, player_key, card_serial)?
+            }
+            PlayerAction::Unstake { card_serial } => {
+                apply_unstake(&mut player, gs, player_key, card_serial)?
+            }
+            PlayerAction::Discard { card_serial } => {
+                // Same rule as DiscardCard, which checks it in its accounts
+                require!(
+                    player.pending_action() == PendingRandomAction::None,
+                    PonzimonError::PendingActionInProgress
+                );
+                apply_discard(&mut player, gs, player_key, card_serial)?
+            }
+            PlayerAction::UpgradeFarm { farm_type } => {
+                // Earlier transfers in this batch (settlement, previous
+                // upgrades) changed the balance since the account was loaded
+                ctx.accounts.player_token_account.reload()?;
+                check_upgrade_cooldown(&player, gs, slot)?;
+                let cost = apply_farm_upgrade(
+                    &mut player,
+                    gs,
+                    &ctx.accounts.farm_config,
+                    farm_type,
+                    ctx.accounts.player_token_account.amount,
+                    slot,
+                )?;
+                let referral_fee = gs.referral_fee_upgrade;
+                handle_fee_transfers(
+                    &mut player,
+                    gs,
+                    cost,
+                    &ctx.accounts.player_token_account.to_account_info(),
+                    &ctx.accounts.fees_token_account.to_account_info(),
+                    ctx.accounts
+                        .referral_vault
+                        .as_ref()
+                        .map(|vault| vault.to_account_info()),
+                    None,
+                    &ctx.accounts.player_wallet.to_account_info(),
+                    &ctx.accounts.token_program.to_account_info(),
+                    &ctx.accounts.token_mint.to_account_info(),
+                    referral_fee,
+                )?;
+                emit!(FarmUpgraded {
+                    player: ctx.accounts.player_wallet.key(),
+                    new_farm_type: farm_type,
+                });
+            }
+            PlayerAction::Claim => check_claim_cooldown(claim_ready, gs, slot)?,
+        }
+    }
+
+    emit!(ActionsExecuted {
+        player: player_key,
+        action_count: actions.len() as u8,
+        rewards_claimed,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  OPTIMIZE LOADOUT (stake the best card subset)
+/// ────────────────────────────────────────────────────────────────────────────
+/// Compute unit limit clients should request for optimize_loadout (the
+/// transaction maximum).
+pub const OPTIMIZE_LOADOUT_COMPUTE_UNITS: u32 = 1_400_000;
+/// Compute units the DP may spend; the rest covers settlement and restaking.
+pub const LOADOUT_DP_COMPUTE_UNITS: usize = 1_100_000;
+/// Upper bound on the cost of one DP step: two table reads, an add, a compare,
+/// a store and a decision bit, with bounds checks.
+pub const LOADOUT_DP_STEP_UNITS: usize = 16;
+/// Heap the DP may use out of the default 32 KiB frame, leaving room for the
+/// candidate lists and event serialization.
+pub const LOADOUT_DP_HEAP_BYTES: usize = 24 * 1024;
+/// One decision bit per DP step.
+pub const MAX_LOADOUT_DP_WORK: usize = LOADOUT_DP_COMPUTE_UNITS / LOADOUT_DP_STEP_UNITS;
+/// 8 bytes per table cell, in whatever heap the decision bits leave.
+pub const MAX_LOADOUT_DP_CELLS: usize = (LOADOUT_DP_HEAP_BYTES - MAX_LOADOUT_DP_WORK / 8) / 8;
+
+#[event]
+pub struct LoadoutOptimized {
+    pub player: Pubkey,
+    pub staked_card_count: u8,
+    pub old_hashpower: u64,
+    pub new_hashpower: u64,
+    pub hashpower_delta: i64,
+    pub berries: u64,
+    pub exact: bool, // False if the input was over the DP limits and a greedy loadout was staked
+}
+
+#[derive(Accounts)]
+pub struct OptimizeLoadout<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Restakes the player's cards to the subset with the most hashpower that
+/// fits farm.total_cards and farm.berry_capacity. Cards tied up in a pending
+/// recycle or fusion are left alone. Inputs too large for the DP budget get a
+/// greedy loadout instead, reported with `exact: false`.
+pub fn optimize_loadout(ctx: Context<OptimizeLoadout>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    // Settle rewards before changing hashpower
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    let (eligible, pending): (Vec<u8>, Vec<u8>) =
+        (0..player.card_count).partition(|&index| !player.is_card_being_recycled(index));
+
+    // Pending cards keep whatever they hold; the optimizer gets what is left
+    let mut new_berries = 0u64;
+    let mut new_hashpower = 0u64;
+    let mut pending_staked = 0u8;
+    for &index in pending
+        .iter()
+        .filter(|&&index| player.is_card_staked(index))
+    {
+        let card = &player.cards[index as usize];
+        new_berries = safe_add_berries(new_berries, card.berry_consumption as u64)?;
+        new_hashpower = safe_add_hashpower(new_hashpower, card.hashpower as u64)?;
+        pending_staked += 1;
+    }
+
+    let candidates: Vec<LoadoutCard> = eligible
+        .iter()
+        .map(|&index| {
+            let card = &player.cards[index as usize];
+            LoadoutCard {
+                hashpower: card.hashpower as u64,
+                berry_consumption: card.berry_consumption as u64,
+            }
+        })
+        .collect();
+    let loadout = optimal_loadout(
+        &candidates,
+        player.farm.total_cards.saturating_sub(pending_staked) as usize,
+        player.farm.berry_capacity.saturating_sub(new_berries),
+        LoadoutLimits {
+            max_cells: MAX_LOADOUT_DP_CELLS,
+            max_work: MAX_LOADOUT_DP_WORK,
+        },
+    );
+    let chosen = &loadout.cards;
+
+    let old_berries = player.berries;
+    let old_hashpower = player.total_hashpower;
+
+    // Effects: restake exactly the chosen subset
+    let mut chosen_iter = chosen.iter().peekable();
+    for (position, &index) in eligible.iter().enumerate() {
+        let stake = chosen_iter.next_if_eq(&&position).is_some();
+        if stake {
+            let card = &player.cards[index as usize];
+            new_berries = safe_add_berries(new_berries, card.berry_consumption as u64)?;
+            new_hashpower = safe_add_hashpower(new_hashpower, card.hashpower as u64)?;
+        }
+        match (player.is_card_staked(index), stake) {
+            (false, true) => player.stake_card(index)?,
+            (true, false) => player.unstake_card(index)?,
+            _ => {}
+        }
+    }
+
+    player.berries = new_berries;
+    player.total_hashpower = new_hashpower;
+    gs.total_berries = safe_add_berries(
+        safe_sub_berries(gs.total_berries, old_berries)?,
+        new_berries,
+    )?;
+    gs.total_hashpower = safe_add_hashpower(
+        safe_sub_hashpower(gs.total_hashpower, old_hashpower)?,
+        new_hashpower,
+    )?;
+
+    emit!(LoadoutOptimized {
+        player: player_key,
+        staked_card_count: pending_staked + chosen.len() as u8,
+        old_hashpower,
+        new_hashpower,
+        hashpower_delta: new_hashpower as i64 - old_hashpower as i64,
+        berries: new_berries,
+        exact: loadout.exact,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct OpenBoosterCommit<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::BoosterAlreadyPending,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = fees_token_account.mint == global_state.token_mint,
+        constraint = fees_token_account.owner == global_state.fees_wallet @ PonzimonError::Unauthorized
+    )]
+    pub fees_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: Only its key is used, to derive referral_vault. Must be the player's recorded referrer.
+    #[account(
+        constraint = Some(referrer_wallet.key()) == player.load()?.referrer() @ PonzimonError::ReferrerAccountMissing
+    )]
+    pub referrer_wallet: Option<AccountInfo<'info>>,
+    /// Required when the player has a referrer and referral_fee is non-zero
+    #[account(
+        init_if_needed,
+        payer = player_wallet,
+        token::mint = token_mint,
+        token::authority = global_state,
+        seeds = [REFERRAL_VAULT_SEED, token_mint.key().as_ref(), referrer_wallet.as_ref().ok_or(PonzimonError::ReferrerAccountMissing)?.key.as_ref()],
+        bump
+    )]
+    pub referral_vault: Option<Box<Account<'info, TokenAccount>>>,
+    /// Omitted until initialize_buyback has run; treated as a 0% share
+    #[account(
+        seeds = [BUYBACK_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub buyback_config: Option<Box<Account<'info, BuybackConfig>>>,
+    /// Required with buyback_config when its fee_share is non-zero
+    #[account(
+        mut,
+        seeds = [BUYBACK_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub buyback_vault: Option<Box<Account<'info, TokenAccount>>>,
+    #[account(mut)]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// With `auto_stake`, settle_open_booster stakes the drawn cards itself while
+/// the farm has free slots and berry capacity. Without a buyback config the
+/// buyback share stays with the protocol fee.
+pub fn open_booster_commit(ctx: Context<OpenBoosterCommit>, auto_stake: bool) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    require!(
+        (player.card_count as usize) + 5 <= MAX_CARDS_PER_PLAYER as usize,
+        PonzimonError::MachineCapacityExceeded
+    );
+
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    // --- Token Fee, Burn, and Referral Logic ---
+    let booster_cost = gs.booster_pack_cost_microtokens;
+    let referral_fee = gs.referral_fee;
+    let buyback_share = ctx
+        .accounts
+        .buyback_config
+        .as_ref()
+        .map_or(0, |config| config.fee_share);
+    let buyback_vault = if buyback_share > 0 {
+        let vault = ctx
+            .accounts
+            .buyback_vault
+            .as_ref()
+            .ok_or(PonzimonError::BuybackUnavailable)?;
+        Some(vault.to_account_info())
+    } else {
+        None
+    };
+
+    // Handle burn and fee transfers with referral logic
+    handle_fee_transfers(
+        &mut player,
+        gs,
+        booster_cost,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.fees_token_account.to_account_info(),
+        ctx.accounts
+            .referral_vault
+            .as_ref()
+            .map(|vault| vault.to_account_info()),
+        buyback_vault.as_ref().map(|vault| (vault, buyback_share)),
+        &ctx.accounts.player_wallet.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        referral_fee,
+    )?;
+
+    // Set player state for settlement
+    player.set_pending_action(PendingRandomAction::Booster);
+    player.commit_slot = slot;
+    player.auto_stake_booster = auto_stake as u8;
+
+    // Update player spending tracking
+    player.total_tokens_spent = player.total_tokens_spent.saturating_add(booster_cost);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SettleOpenBooster<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::Booster @ PonzimonError::NoBoosterPending,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+    /// CHECK: Checked manually, otherwise it exceeds CU
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+/// The SlotHashes entry for `reveal_slot`, the randomness every commit/settle
+/// pair draws from. The sysvar is parsed by hand because deserializing it
+/// whole exceeds the compute budget.
+fn reveal_hash(slot_hashes: &AccountInfo, reveal_slot: u64) -> Result<[u8; 32]> {
+    require!(
+        slot_hashes.key == &slot_hashes::id(),
+        PonzimonError::InvalidSlotHashes
+    );
+
+    let data = slot_hashes.try_borrow_data()?;
+    let num_slot_hashes = u64::from_le_bytes(data[0..8].try_into().unwrap());
+    let mut pos = 8;
+    for _ in 0..num_slot_hashes {
+        let slot = u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap());
+        pos += 8;
+        if slot == reveal_slot {
+            return Ok(data[pos..pos + 32].try_into().unwrap());
+        }
+        pos += 32;
+    }
+
+    Err(PonzimonError::SlotNotFound.into())
+}
+
+pub fn settle_open_booster(ctx: Context<SettleOpenBooster>) -> Result<()> {
+    let clock: Clock = Clock::get()?;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+    let catalog = ctx.accounts.card_catalog.load()?;
+
+    require!(
+        clock.slot >= player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS,
+        PonzimonError::RandomnessNotResolved
+    );
+    let reveal_slot = player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS;
+
+    let random_value = reveal_hash(&ctx.accounts.slot_hashes, reveal_slot)?;
+
+    // Settle rewards before changing berry consumption
+    update_pool(gs, clock.slot);
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+
+    // First pass: roll a rarity for each of the 5 slots
+    let mut rarities = [COMMON; 5];
+    let mut random_u32s = [0u32; 5];
+    for i in 0..5 {
+        // Use a different slice of the random value for each card
+        let slice_start = i * 4;
+        let slice_end = slice_start + 4;
+        let mut random_bytes: [u8; 4] = [0; 4];
+        random_bytes.copy_from_slice(&random_value[slice_start..slice_end]);
+        let random_u32 = u32::from_le_bytes(random_bytes);
+        random_u32s[i] = random_u32;
+
+        rarities[i] = ponzimon_math::roll_booster_rarity(random_u32);
+    }
+
+    // Pity: if this pack would be the Nth in a row without a card at or above the
+    // pity rarity, force the last slot up to it. A threshold of 0 disables pity.
+    let pity_triggered = ponzimon_math::apply_pity(
+        &mut rarities,
+        &mut player.boosters_without_pity,
+        gs.pity_rarity,
+        gs.pity_threshold,
+    );
+
+    // Second pass: pick a concrete card for each rolled rarity
+    let mut card_ids = [0u16; 5];
+    let mut drawn: Vec<Card> = Vec::with_capacity(5);
+    for i in 0..5 {
+        let rarity = rarities[i];
+        let random_u32 = random_u32s[i];
+
+        // Find a random active catalog card of the determined rarity
+        let cards_of_rarity = catalog.active_of_rarity(rarity);
+
+        if !cards_of_rarity.is_empty() {
+            let card_index = ponzimon_math::pick_index(random_u32, cards_of_rarity.len());
+
+            let entry = cards_of_rarity[card_index];
+
+            require!(
+                (player.card_count as usize) < MAX_CARDS_PER_PLAYER as usize,
+                PonzimonError::MachineCapacityExceeded
+            );
+
+            let card = mint_card(gs, entry.to_card());
+            player.add_card(card)?;
+            drawn.push(card);
+            card_ids[i] = entry.id;
+        }
+    }
+
+    // Auto-stake: best hashpower per berry first, skipping cards that no longer fit.
+    // The pool was updated above, so staking here doesn't skew accrued rewards.
+    if player.auto_stake_booster != 0 {
+        drawn.sort_by(|a, b| {
+            (b.hashpower as u64 * a.berry_consumption.max(1) as u64)
+                .cmp(&(a.hashpower as u64 * b.berry_consumption.max(1) as u64))
+        });
+        for card in &drawn {
+            let fits = check_stake(
+                player.count_staked_cards(),
+                player.farm.total_cards,
+                player.berries,
+                player.farm.berry_capacity,
+                card.berry_consumption as u64,
+            )
+            .is_ok();
+            if fits {
+                apply_stake(&mut player, gs, player_key, card.serial)?;
+            }
+        }
+    }
+
+    // Reset booster state
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+    player.auto_stake_booster = 0;
+
+    // Update tracking statistics
+    player.total_booster_packs_opened = player.total_booster_packs_opened.saturating_add(1);
+    gs.total_booster_packs_opened = gs.total_booster_packs_opened.saturating_add(1);
+
+    emit!(BoosterOpened {
+        player: player_key,
+        card_types: card_ids.map(|id| id as u8), // Convert to u8 for compatibility with event size limits
+        pity_triggered,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  VIEWS (simulate-only, results via set_return_data)
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PendingRewardsView {
+    pub slot: u64,
+    pub pending_rewards: u64,
+    pub acc_tokens_per_hashpower: u128,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PlayerSummaryView {
+    pub owner: Pubkey,
+    pub farm_type: u8,
+    pub farm_total_cards: u8,
+    pub farm_berry_capacity: u64,
+    pub card_count: u8,
+    pub staked_card_count: u8,
+    pub berries: u64,
+    pub total_hashpower: u64,
+    pub pending_rewards: u64,
+    pub total_rewards: u64,
+    pub referrer: Option<Pubkey>,
+    pub pending_action: PendingRandomAction,
+    pub commit_slot: u64,
+    pub boosters_without_pity: u8,
+    pub total_booster_packs_opened: u64,
+    pub total_cards_recycled: u64,
+    pub successful_card_recycling: u64,
+    pub shards: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GlobalStatsView {
+    pub slot: u64,
+    pub production_enabled: bool,
+    pub total_supply: u64,
+    pub cumulative_rewards: u64,
+    pub burned_tokens: u64,
+    pub remaining_supply: u64,
+    pub reward_rate: u64,
+    pub acc_tokens_per_hashpower: u128,
+    pub total_hashpower: u64,
+    pub total_berries: u64,
+    pub total_booster_packs_opened: u64,
+    pub total_card_recycling_attempts: u64,
+    pub total_successful_card_recycling: u64,
+}
+
+#[derive(Accounts)]
+#[instruction(owner: Pubkey)]
+pub struct PlayerView<'info> {
+    #[account(
+        seeds = [PLAYER_SEED, owner.as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct GlobalView<'info> {
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+/// Copy of the global state advanced to `slot_now`, so views report the same
+/// numbers a settling instruction would without writing anything.
+fn projected_global_state(gs: &GlobalState, slot_now: u64) -> GlobalState {
+    let mut gs = gs.clone();
+    update_pool(&mut gs, slot_now);
+    gs
+}
+
+/// Returns the player's claimable rewards as a Borsh-encoded PendingRewardsView.
+/// `_owner` is the player's wallet and only serves to derive the Player PDA.
+pub fn get_pending_rewards(ctx: Context<PlayerView>, _owner: Pubkey) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player = ctx.accounts.player.load()?;
+    let gs = projected_global_state(&ctx.accounts.global_state, slot);
+
+    let view = PendingRewardsView {
+        slot,
+        pending_rewards: if slot < gs.start_slot {
+            0
+        } else {
+            pending_rewards(&player, &gs).saturating_add(player.accrued_rewards)
+        },
+        acc_tokens_per_hashpower: gs.acc_tokens_per_hashpower,
+    };
+
+    set_return_data(&view.try_to_vec()?);
+    Ok(())
+}
+
+/// Returns the player's farm, inventory and reward totals as a Borsh-encoded
+/// PlayerSummaryView. `_owner` derives the Player PDA as in get_pending_rewards.
+pub fn get_player_summary(ctx: Context<PlayerView>, _owner: Pubkey) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player = ctx.accounts.player.load()?;
+    let gs = projected_global_state(&ctx.accounts.global_state, slot);
+
+    let view = PlayerSummaryView {
+        owner: player.owner,
+        farm_type: player.farm.farm_type,
+        farm_total_cards: player.farm.total_cards,
+        farm_berry_capacity: player.farm.berry_capacity,
+        card_count: player.card_count,
+        staked_card_count: player.count_staked_cards(),
+        berries: player.berries,
+        total_hashpower: player.total_hashpower,
+        pending_rewards: if slot < gs.start_slot {
+            0
+        } else {
+            pending_rewards(&player, &gs).saturating_add(player.accrued_rewards)
+        },
+        total_rewards: player.total_rewards,
+        referrer: player.referrer(),
+        pending_action: player.pending_action(),
+        commit_slot: player.commit_slot,
+        boosters_without_pity: player.boosters_without_pity,
+        total_booster_packs_opened: player.total_booster_packs_opened,
+        total_cards_recycled: player.total_cards_recycled,
+        successful_card_recycling: player.successful_card_recycling,
+        shards: player.shards,
+    };
+
+    set_return_data(&view.try_to_vec()?);
+    Ok(())
+}
+
+/// Returns supply, emission and activity totals as a Borsh-encoded GlobalStatsView.
+pub fn get_global_stats(ctx: Context<GlobalView>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let gs = projected_global_state(&ctx.accounts.global_state, slot);
+
+    let view = GlobalStatsView {
+        slot,
+        production_enabled: gs.production_enabled,
+        total_supply: gs.total_supply,
+        cumulative_rewards: gs.cumulative_rewards,
+        burned_tokens: gs.burned_tokens,
+        remaining_supply: remaining_supply(&gs),
+        reward_rate: gs.reward_rate,
+        acc_tokens_per_hashpower: gs.acc_tokens_per_hashpower,
+        total_hashpower: gs.total_hashpower,
+        total_berries: gs.total_berries,
+        total_booster_packs_opened: gs.total_booster_packs_opened,
+        total_card_recycling_attempts: gs.total_card_recycling_attempts,
+        total_successful_card_recycling: gs.total_successful_card_recycling,
+    };
+
+    set_return_data(&view.try_to_vec()?);
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ToggleProduction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = authority @ PonzimonError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn toggle_production(ctx: Context<ToggleProduction>, enable: bool) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    global_state.production_enabled = enable;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateParameters<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = authority @ PonzimonError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+/// Updates a single parameter in the global state.
+///
+/// # Arguments
+///
+/// * `ctx` - The context for the instruction.
+/// * `parameter_index` - The index of the parameter to update:
+///     - 0: ReferralFee (u8)
+///     - 1: BurnRate (u8)
+///     - 2: DustThresholdDivisor (u64)
+///     - 3: InitialFarmPurchaseFeeLamports (u64)
+///     - 4: BoosterPackCostMicrotokens (u64)
+///     - 5: GambleFeeLamports (u64)
+///     - 6: StakingLockupSlots (u64)
+///     - 7: TokenRewardRate (u64)
+///     - 8: RewardRate (u64)
+///     - 9: PityRarity (u8)
+///     - 10: PityThreshold (u8, 0 disables pity)
+///     - 11..=16: RecycleUpgradeOddsBps (u16 basis points) for source rarity
+///       `parameter_index - 11` (COMMON … SUPER_RARE)
+///     - 17..=23: DiscardShards (u16) for rarity `parameter_index - 17`
+///     - 24: CraftBoosterShards (u16, 0 disables crafting boosters)
+///     - 25..=31: CraftCardShards (u16, 0 disables crafting that rarity) for
+///       rarity `parameter_index - 25`
+///     - 32: UpgradeCooldownSlots (u32, 0 disables)
+///     - 33: ClaimCooldownSlots (u32, 0 disables)
+///     - 34: ReferralFeeInitialFarm (percent of the SOL purchase fee)
+///     - 35: ReferralFeeUpgrade (percent of the non-burned upgrade fees)
+/// * `parameter_value` - The new value for the parameter.
+pub fn update_parameter(
+    ctx: Context<UpdateParameters>,
+    parameter_index: u8,
+    parameter_value: u64,
+) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+
+    match parameter_index {
+        0 => {
+            // ReferralFee
+            require!(parameter_value <= 100, PonzimonError::InvalidReferralFee);
+            global_state.referral_fee = parameter_value as u8;
+        }
+        1 => {
+            // BurnRate
+            require!(parameter_value <= 100, PonzimonError::InvalidBurnRate);
+            global_state.burn_rate = parameter_value as u8;
+        }
+        2 => {
+            // DustThresholdDivisor
+            require!(
+                parameter_value > 0,
+                PonzimonError::InvalidDustThresholdDivisor
+            );
+            global_state.dust_threshold_divisor = parameter_value;
+        }
+        3 => {
+            // InitialFarmPurchaseFeeLamports
+            global_state.initial_farm_purchase_fee_lamports = parameter_value;
+        }
+        4 => {
+            // BoosterPackCostMicrotokens
+            global_state.booster_pack_cost_microtokens = parameter_value;
+        }
+        5 => {
+            // GambleFeeLamports
+            global_state.gamble_fee_lamports = parameter_value;
+        }
+        6 => {
+            // StakingLockupSlots
+            global_state.staking_lockup_slots = parameter_value;
+        }
+        7 => {
+            // TokenRewardRate
+            global_state.token_reward_rate = parameter_value;
+        }
+        8 => {
+            // RewardRate
+            global_state.reward_rate = parameter_value;
+        }
+        9 => {
+            // PityRarity
+            require!(
+                parameter_value <= MEGA_RARE as u64,
+                PonzimonError::InvalidPityRarity
+            );
+            global_state.pity_rarity = parameter_value as u8;
+        }
+        10 => {
+            // PityThreshold
+            require!(
+                parameter_value <= u8::MAX as u64,
+                PonzimonError::InvalidPityThreshold
+            );
+            global_state.pity_threshold = parameter_value as u8;
+        }
+        11..=16 => {
+            // RecycleUpgradeOddsBps. The first write stores the default table
+            // it replaces, so the other rarities keep the odds they had.
+            require!(
+                parameter_value <= BPS_DENOMINATOR,
+                PonzimonError::InvalidRecycleOdds
+            );
+            if !global_state.recycle_odds_initialized {
+                global_state.recycle_upgrade_odds_bps = global_state.recycle_upgrade_odds();
+                global_state.recycle_odds_initialized = true;
+            }
+            let source_rarity = (parameter_index - 11) as usize;
+            global_state.recycle_upgrade_odds_bps[source_rarity] = parameter_value as u16;
+        }
+        17..=23 => {
+            // DiscardShards
+            require!(
+                parameter_value <= u16::MAX as u64,
+                PonzimonError::InvalidShardAmount
+            );
+            let rarity = (parameter_index - 17) as usize;
+            global_state.discard_shards[rarity] = parameter_value as u16;
+        }
+        24 => {
+            // CraftBoosterShards
+            require!(
+                parameter_value <= u16::MAX as u64,
+                PonzimonError::InvalidShardAmount
+            );
+            global_state.craft_booster_shards = parameter_value as u16;
+        }
+        25..=31 => {
+            // CraftCardShards
+            require!(
+                parameter_value <= u16::MAX as u64,
+                PonzimonError::InvalidShardAmount
+            );
+            let rarity = (parameter_index - 25) as usize;
+            global_state.craft_card_shards[rarity] = parameter_value as u16;
+        }
+        32 => {
+            // UpgradeCooldownSlots
+            require!(
+                parameter_value <= u32::MAX as u64,
+                PonzimonError::InvalidCooldown
+            );
+            global_state.upgrade_cooldown_slots = parameter_value as u32;
+        }
+        33 => {
+            // ClaimCooldownSlots
+            require!(
+                parameter_value <= u32::MAX as u64,
+                PonzimonError::InvalidCooldown
+            );
+            global_state.claim_cooldown_slots = parameter_value as u32;
+        }
+        34 => {
+            // ReferralFeeInitialFarm
+            require!(parameter_value <= 100, PonzimonError::InvalidReferralFee);
+            global_state.referral_fee_initial_farm = parameter_value as u8;
+        }
+        35 => {
+            // ReferralFeeUpgrade
+            require!(parameter_value <= 100, PonzimonError::InvalidReferralFee);
+            global_state.referral_fee_upgrade = parameter_value as u8;
+        }
+        _ => return err!(PonzimonError::InvalidParameterIndex),
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdatePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = authority @ PonzimonError::Unauthorized
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+pub fn update_pool_manual(ctx: Context<UpdatePool>) -> Result<()> {
+    let global_state = &mut ctx.accounts.global_state;
+    let slot_now: u64 = Clock::get()?.slot;
+
+    update_pool(global_state, slot_now);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct ResetPlayer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        mut,
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        seeds = [FARM_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub farm_config: Box<Account<'info, FarmConfig>>,
+    pub token_mint: Account<'info, Mint>,
+    /// CHECK: This is just a system account
+    pub player_wallet: AccountInfo<'info>,
+}
+
+pub fn reset_player(ctx: Context<ResetPlayer>) -> Result<()> {
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+    let slot = Clock::get()?.slot;
+
+    // Update pool to current slot
+    update_pool(gs, slot);
+
+    // Store the old berry consumption and power to update global state
+    let old_berries = player.berries;
+    let old_power = player.total_hashpower;
+
+    // Reset player's berry consumption, power, and farm
+    player.berries = 0;
+    player.total_hashpower = 0;
+    let tier = ctx.accounts.farm_config.tier(0)?;
+    player.farm = Farm {
+        farm_type: 0,
+        total_cards: tier.total_cards,
+        berry_capacity: tier.berry_capacity,
+    };
+    player.cards = [Card::default(); MAX_CARDS_PER_PLAYER as usize]; // Clear all cards
+    player.card_count = 0;
+    player.staked_cards_bitset = 0; // Clear all staked cards
+    player.locked_cards_bitset = 0;
+    player.boosters_without_pity = 0;
+
+    // Update global berry consumption and power
+    gs.total_berries = gs.total_berries.saturating_sub(old_berries);
+    gs.total_hashpower = gs.total_hashpower.saturating_sub(old_power);
+
+    // Update player's last claim slot and accumulator
+    player.last_claim_slot = slot;
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+    player.accrued_rewards = 0;
+
+    // Reset any pending operations
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  FARM CONFIG (tier table, editable by the authority)
+/// ────────────────────────────────────────────────────────────────────────────
+pub const FARM_CONFIG_SEED: &[u8] = b"farm_config";
+pub const MAX_FARM_TIERS: usize = 16;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct FarmTier {
+    pub total_cards: u8,
+    pub berry_capacity: u64,
+    /// Token cost of upgrading into this tier
+    pub upgrade_cost: u64,
+}
+
+impl FarmTier {
+    pub const SIZE: usize = 1 + 8 + 8;
+}
+
+/// Tier table indexed by farm_type. Tier 0 is the reset farm and tier 1 the
+/// farm bought with purchase_initial_farm; upgrades walk up from there.
+#[account]
+pub struct FarmConfig {
+    pub tier_count: u8,
+    pub tiers: [FarmTier; MAX_FARM_TIERS],
+}
+
+impl FarmConfig {
+    pub const SIZE: usize = 8 /* discriminator */
+        + 1 /* tier_count */
+        + MAX_FARM_TIERS * FarmTier::SIZE;
+
+    pub fn tier(&self, farm_type: u8) -> Result<FarmTier> {
+        require!(farm_type < self.tier_count, PonzimonError::InvalidFarmType);
+        Ok(self.tiers[farm_type as usize])
+    }
+
+    pub fn active_tiers(&self) -> &[FarmTier] {
+        &self.tiers[..self.tier_count as usize]
+    }
+
+    /// Replaces the table. Slots, berry capacity and cost may never go down
+    /// from one tier to the next, and tiers can be added but not removed so
+    /// no player is left on a farm_type that no longer exists.
+    fn set_tiers(&mut self, tiers: &[FarmTier]) -> Result<()> {
+        require!(
+            tiers.len() >= 2
+                && tiers.len() <= MAX_FARM_TIERS
+                && tiers.len() >= self.tier_count as usize,
+            PonzimonError::InvalidFarmConfig
+        );
+        for tier in tiers {
+            require!(
+                tier.total_cards as usize <= MAX_CARDS_PER_PLAYER as usize,
+                PonzimonError::InvalidFarmConfig
+            );
+        }
+        for pair in tiers.windows(2) {
+            require!(
+                pair[1].total_cards >= pair[0].total_cards
+                    && pair[1].berry_capacity >= pair[0].berry_capacity
+                    && pair[1].upgrade_cost >= pair[0].upgrade_cost,
+                PonzimonError::InvalidFarmConfig
+            );
+        }
+
+        self.tiers = [FarmTier::default(); MAX_FARM_TIERS];
+        self.tiers[..tiers.len()].copy_from_slice(tiers);
+        self.tier_count = tiers.len() as u8;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct FarmConfigUpdated {
+    pub tiers: Vec<FarmTier>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFarmConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init,
+        payer = authority,
+        space = FarmConfig::SIZE,
+        seeds = [FARM_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub farm_config: Box<Account<'info, FarmConfig>>,
+    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the farm config seeded with the compiled FARM_CONFIGS table so
+/// existing tiers keep their values after the switch. purchase_initial_farm,
+/// upgrade_farm, upgrade_farm_to, reset_player and execute_actions all require
+/// this account, so the authority must run this right after deploying the
+/// upgrade that introduces it.
+pub fn initialize_farm_config(ctx: Context<InitializeFarmConfig>) -> Result<()> {
+    let tiers: Vec<FarmTier> = FARM_CONFIGS
+        .iter()
+        .map(|&(total_cards, berry_capacity, upgrade_cost)| FarmTier {
+            total_cards,
+            berry_capacity,
+            upgrade_cost,
+        })
+        .collect();
+
+    let farm_config = &mut ctx.accounts.farm_config;
+    farm_config.tier_count = 0;
+    farm_config.set_tiers(&tiers)?;
+
+    emit!(FarmConfigUpdated { tiers });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateFarmConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [FARM_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub farm_config: Box<Account<'info, FarmConfig>>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+/// Replaces the whole tier table. Existing farms keep the slots and capacity
+/// they were upgraded with; the new values apply from their next upgrade.
+pub fn update_farm_config(ctx: Context<UpdateFarmConfig>, tiers: Vec<FarmTier>) -> Result<()> {
+    ctx.accounts.farm_config.set_tiers(&tiers)?;
+
+    emit!(FarmConfigUpdated { tiers });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  CARD CATALOG
+/// ────────────────────────────────────────────────────────────────────────────
+pub const CARD_CATALOG_SEED: &[u8] = b"card_catalog";
+pub const MAX_CATALOG_ENTRIES: usize = 512;
+
+#[zero_copy(unsafe)]
+#[repr(C, packed)]
+#[derive(Default, PartialEq, Eq)]
+pub struct CatalogEntry {
+    pub id: u16,
+    pub rarity: u8,
+    pub hashpower: u16,
+    pub berry_consumption: u8,
+    pub retired: u8, // Non-zero: never drawn, but existing cards keep working
+}
+
+// Borsh for the add_catalog_cards argument. Written by hand for the same
+// reason as Card's: the derive borrows fields of a packed struct.
+impl AnchorSerialize for CatalogEntry {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let CatalogEntry {
+            id,
+            rarity,
+            hashpower,
+            berry_consumption,
+            retired,
+        } = *self;
+        id.serialize(writer)?;
+        rarity.serialize(writer)?;
+        hashpower.serialize(writer)?;
+        berry_consumption.serialize(writer)?;
+        retired.serialize(writer)
+    }
+}
+
+impl AnchorDeserialize for CatalogEntry {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(CatalogEntry {
+            id: u16::deserialize_reader(reader)?,
+            rarity: u8::deserialize_reader(reader)?,
+            hashpower: u16::deserialize_reader(reader)?,
+            berry_consumption: u8::deserialize_reader(reader)?,
+            retired: u8::deserialize_reader(reader)?,
+        })
+    }
+}
+
+impl CatalogEntry {
+    pub const SIZE: usize = 2 + 1 + 2 + 1 + 1;
+
+    pub fn to_card(&self) -> Card {
+        Card {
+            id: self.id,
+            rarity: self.rarity,
+            hashpower: self.hashpower,
+            berry_consumption: self.berry_consumption,
+            serial: 0, // Assigned by mint_card
+        }
+    }
+}
+
+/// Zero-copy: 512 entries are too big to deserialize onto the stack.
+#[account(zero_copy(unsafe))]
+#[repr(C, packed)]
+pub struct CardCatalog {
+    pub entry_count: u16,
+    pub entries: [CatalogEntry; MAX_CATALOG_ENTRIES],
+}
+
+impl CardCatalog {
+    pub const SIZE: usize = 8 /* discriminator */
+        + 2 /* entry_count */
+        + MAX_CATALOG_ENTRIES * CatalogEntry::SIZE;
+
+    pub fn get(&self, card_id: u16) -> Option<&CatalogEntry> {
+        self.entries[..self.entry_count as usize]
+            .iter()
+            .find(|entry| entry.id == card_id)
+    }
+
+    pub fn active_of_rarity(&self, rarity: u8) -> Vec<&CatalogEntry> {
+        self.entries[..self.entry_count as usize]
+            .iter()
+            .filter(|entry| entry.retired == 0 && entry.rarity == rarity)
+            .collect()
+    }
+
+    fn append(&mut self, entry: CatalogEntry) -> Result<()> {
+        require!(
+            (self.entry_count as usize) < MAX_CATALOG_ENTRIES,
+            PonzimonError::CatalogFull
+        );
+        require!(
+            self.get(entry.id).is_none(),
+            PonzimonError::DuplicateCatalogCard
+        );
+        require!(entry.rarity <= MEGA_RARE, PonzimonError::InvalidCardRarity);
+        self.entries[self.entry_count as usize] = entry;
+        self.entry_count += 1;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct CatalogUpdated {
+    pub card_id: u16,
+    pub retired: bool,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCardCatalog<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init,
+        payer = authority,
+        space = CardCatalog::SIZE,
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Creates the catalog and seeds it with the compiled CARD_DATA table so
+/// existing draws keep their odds after the switch.
+pub fn initialize_card_catalog(ctx: Context<InitializeCardCatalog>) -> Result<()> {
+    let mut catalog = ctx.accounts.card_catalog.load_init()?;
+
+    for &(id, rarity, hashpower, berry_consumption) in CARD_DATA.iter() {
+        catalog.append(CatalogEntry {
+            id,
+            rarity,
+            hashpower,
+            berry_consumption,
+            retired: 0,
+        })?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateCardCatalog<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+/// Appends new cards to the catalog. Card ids must be unique across the whole
+/// catalog, including retired entries.
+pub fn add_catalog_cards(
+    ctx: Context<UpdateCardCatalog>,
+    entries: Vec<CatalogEntry>,
+) -> Result<()> {
+    let mut catalog = ctx.accounts.card_catalog.load_mut()?;
+
+    for mut entry in entries {
+        entry.retired = 0;
+        catalog.append(entry)?;
+
+        emit!(CatalogUpdated {
+            card_id: entry.id,
+            retired: false,
+        });
+    }
+
+    Ok(())
+}
+
+/// Retires (or restores) a catalog entry. Retired cards are no longer drawn by
+/// boosters or recycling; cards already owned by players are unaffected.
+/// Every rarity must keep at least one active card, otherwise draws of that
+/// rarity would have nothing to mint.
+pub fn set_catalog_card_retired(
+    ctx: Context<UpdateCardCatalog>,
+    card_id: u16,
+    retired: bool,
+) -> Result<()> {
+    let mut catalog = ctx.accounts.card_catalog.load_mut()?;
+    let count = catalog.entry_count as usize;
+
+    let rarity = catalog
+        .get(card_id)
+        .ok_or(PonzimonError::CardNotInCatalog)?
+        .rarity;
+    if retired {
+        let still_active = catalog
+            .active_of_rarity(rarity)
+            .iter()
+            .any(|entry| entry.id != card_id);
+        require!(still_active, PonzimonError::LastActiveCardOfRarity);
+    }
+
+    let entry = catalog.entries[..count]
+        .iter_mut()
+        .find(|entry| entry.id == card_id)
+        .ok_or(PonzimonError::CardNotInCatalog)?;
+    entry.retired = retired as u8;
+
+    emit!(CatalogUpdated { card_id, retired });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  CARD PAGES (overflow inventory)
+/// ────────────────────────────────────────────────────────────────────────────
+pub const CARD_PAGE_SEED: &[u8] = b"card_page";
+pub const CARDS_PER_PAGE: usize = 64;
+
+/// Overflow storage for cards that are not in use on the farm. Cards in a page
+/// can't be staked, recycled or discarded until they are moved back.
+#[account]
+pub struct CardPage {
+    pub player: Pubkey,
+    pub page: u8,
+    pub card_count: u8,
+    pub cards: [Card; CARDS_PER_PAGE],
+}
+
+impl CardPage {
+    pub const SIZE: usize = 8 /* discriminator */
+        + 32 /* player */
+        + 1 + 1 /* page + card_count */
+        + CARDS_PER_PAGE * CARD_SIZE;
+}
+
+#[event]
+pub struct CardsMovedToPage {
+    pub player: Pubkey,
+    pub page: u8,
+    pub card_serials: Vec<u64>,
+}
+
+#[event]
+pub struct CardsMovedFromPage {
+    pub player: Pubkey,
+    pub page: u8,
+    pub card_serials: Vec<u64>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u8)]
+pub struct InitializeCardPage<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        init,
+        payer = player_wallet,
+        space = CardPage::SIZE,
+        seeds = [CARD_PAGE_SEED, player.key().as_ref(), &[page]],
+        bump
+    )]
+    pub card_page: Box<Account<'info, CardPage>>,
+    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_card_page(ctx: Context<InitializeCardPage>, page: u8) -> Result<()> {
+    let card_page = &mut ctx.accounts.card_page;
+    card_page.player = ctx.accounts.player.key();
+    card_page.page = page;
+    card_page.card_count = 0;
+    card_page.cards = [Card::default(); CARDS_PER_PAGE];
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(page: u8)]
+pub struct MoveCardsPage<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        // Pending actions hold card indices, which moving cards would invalidate
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::PendingActionInProgress,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        has_one = player @ PonzimonError::Unauthorized,
+        seeds = [CARD_PAGE_SEED, player.key().as_ref(), &[page]],
+        bump
+    )]
+    pub card_page: Box<Account<'info, CardPage>>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+/// Moves unstaked cards from the farm inventory into a card page.
+pub fn move_cards_to_page(
+    ctx: Context<MoveCardsPage>,
+    page: u8,
+    card_serials: Vec<u64>,
+) -> Result<()> {
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let card_page = &mut ctx.accounts.card_page;
+
+    require!(
+        !card_serials.is_empty()
+            && card_page.card_count as usize + card_serials.len() <= CARDS_PER_PAGE,
+        PonzimonError::CardPageFull
+    );
+
+    let mut card_indices = Vec::with_capacity(card_serials.len());
+    for &serial in &card_serials {
+        let index = find_card_by_serial(&player, serial)?;
+        require!(
+            !card_indices.contains(&index),
+            PonzimonError::DuplicateRecycleCardIndices
+        );
+        require!(!player.is_card_staked(index), PonzimonError::CardIsStaked);
+        // The lock bitset lives on Player, so a locked card can't leave it
+        require!(!player.is_card_locked(index), PonzimonError::CardLocked);
+        card_indices.push(index);
+    }
+
+    for &index in &card_indices {
+        let slot = card_page.card_count as usize;
+        card_page.cards[slot] = player.cards[index as usize];
+        card_page.card_count += 1;
+    }
+    remove_cards(&mut player, &card_indices)?;
+
+    emit!(CardsMovedToPage {
+        player: player_key,
+        page,
+        card_serials,
+    });
+
+    Ok(())
+}
+
+/// Moves cards from a card page back into the farm inventory, unstaked.
+pub fn move_cards_from_page(
+    ctx: Context<MoveCardsPage>,
+    page: u8,
+    card_serials: Vec<u64>,
+) -> Result<()> {
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let card_page = &mut ctx.accounts.card_page;
+
+    require!(
+        !card_serials.is_empty()
+            && player.card_count as usize + card_serials.len() <= MAX_CARDS_PER_PLAYER as usize,
+        PonzimonError::MachineCapacityExceeded
+    );
+
+    for &serial in &card_serials {
+        let count = card_page.card_count as usize;
+        let position = card_page.cards[..count]
+            .iter()
+            .position(|card| serial != 0 && card.serial == serial)
+            .ok_or(PonzimonError::CardNotFound)?;
+
+        player.add_card(card_page.cards[position])?;
+
+        // Keep the page compact: move the last card into the freed slot
+        card_page.cards[position] = card_page.cards[count - 1];
+        card_page.cards[count - 1] = Card::default();
+        card_page.card_count -= 1;
+    }
+
+    emit!(CardsMovedFromPage {
+        player: player_key,
+        page,
+        card_serials,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  MIGRATE CARD SERIALS
+/// ────────────────────────────────────────────────────────────────────────────
+#[event]
+pub struct CardSerialsMigrated {
+    pub player: Pubkey,
+    pub first_serial: u64,
+    pub card_count: u8,
+}
+
+#[derive(Accounts)]
+pub struct MigrateCardSerials<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    /// CHECK: Legacy Player layout can't be deserialized as Account<Player>; the
+    /// owner, discriminator, size and stored owner key are checked manually.
+    #[account(
+        mut,
+        owner = crate::ID @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Converts a Player account from the 6-byte legacy card layout to the 14-byte
+/// layout with serials, growing the account in place and stamping every
+/// existing card with a fresh serial. The wallet pays the extra rent.
+pub fn migrate_card_serials(ctx: Context<MigrateCardSerials>) -> Result<()> {
+    let player_info = ctx.accounts.player.to_account_info();
+    let gs = &mut ctx.accounts.global_state;
+
+    // discriminator (8) + owner (32) + farm (10)
+    const CARDS_OFFSET: usize = 8 + 32 + 10;
+    let max_cards = MAX_CARDS_PER_PLAYER as usize;
+    let old_tail_offset = CARDS_OFFSET + max_cards * LEGACY_CARD_SIZE;
+    let new_tail_offset = CARDS_OFFSET + max_cards * CARD_SIZE;
+    let growth = new_tail_offset - old_tail_offset;
+
+    let old_len = player_info.data_len();
+    let card_count = {
+        let data = player_info.try_borrow_data()?;
+        require!(
+            data[..8] == Player::DISCRIMINATOR[..],
+            PonzimonError::InvalidPlayerAccount
+        );
+        require!(
+            data[8..40] == ctx.accounts.player_wallet.key().to_bytes(),
+            PonzimonError::Unauthorized
+        );
+        // A legacy account ends before the widened cards array would; a migrated one can't
+        require!(old_len < new_tail_offset, PonzimonError::AlreadyMigrated);
+        data[old_tail_offset] as usize
+    };
+    require!(card_count <= max_cards, PonzimonError::InvalidPlayerAccount);
+
+    // Fund and grow the account
+    let new_len = old_len + growth;
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(player_info.lamports());
+    if rent_due > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.player_wallet.to_account_info(),
+                    to: player_info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    player_info.realloc(new_len, true)?;
+
+    let first_serial = gs.next_card_serial.max(1);
+    {
+        let mut data = player_info.try_borrow_mut_data()?;
+
+        // Move everything after the cards array to its new offset
+        data.copy_within(old_tail_offset..old_len, new_tail_offset);
+
+        // Widen cards back to front so no unread legacy card is overwritten
+        for i in (0..max_cards).rev() {
+            let old_offset = CARDS_OFFSET + i * LEGACY_CARD_SIZE;
+            let new_offset = CARDS_OFFSET + i * CARD_SIZE;
+            data.copy_within(old_offset..old_offset + LEGACY_CARD_SIZE, new_offset);
+
+            let serial = if i < card_count {
+                first_serial + i as u64
+            } else {
+                0
+            };
+            data[new_offset + LEGACY_CARD_SIZE..new_offset + CARD_SIZE]
+                .copy_from_slice(&serial.to_le_bytes());
+        }
+    }
+    gs.next_card_serial = first_serial + card_count as u64;
+
+    emit!(CardSerialsMigrated {
+        player: player_info.key(),
+        first_serial,
+        card_count: card_count as u8,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  MIGRATE PLAYER TO ZERO-COPY
+/// ────────────────────────────────────────────────────────────────────────────
+/// Borsh layout of Player before the zero-copy switch (cards already carrying
+/// serials, see migrate_card_serials). Only used to read legacy accounts.
+#[derive(AnchorDeserialize)]
+pub struct LegacyCard {
+    pub id: u16,
+    pub rarity: u8,
+    pub hashpower: u16,
+    pub berry_consumption: u8,
+    pub serial: u64,
+}
+
+#[derive(AnchorDeserialize)]
+pub struct LegacyFarm {
+    pub farm_type: u8,
+    pub total_cards: u8,
+    pub berry_capacity: u64,
+}
+
+/// Fields of the legacy Player stored before its card array.
+#[derive(AnchorDeserialize)]
+pub struct LegacyPlayerHead {
+    pub owner: Pubkey,
+    pub farm: LegacyFarm,
+}
+
+/// Fields of the legacy Player stored after its `MAX_CARDS_PER_PLAYER` cards.
+/// The cards are read one at a time instead, so the whole account never sits
+/// on the 4 KiB BPF stack.
+#[derive(AnchorDeserialize)]
+pub struct LegacyPlayerTail {
+    pub card_count: u8,
+    pub staked_cards_bitset: u128,
+    pub berries: u64,
+    pub total_hashpower: u64,
+    pub referrer: Option<Pubkey>,
+    pub last_acc_tokens_per_hashpower: u128,
+    pub last_claim_slot: u64,
+    pub last_upgrade_slot: u64,
+    pub total_rewards: u64,
+    pub total_gambles: u64,
+    pub total_gamble_wins: u64,
+    pub pending_action: PendingRandomAction,
+    pub commit_slot: u64,
+    pub total_earnings_for_referrer: u64,
+    pub total_booster_packs_opened: u64,
+    pub total_cards_recycled: u64,
+    pub successful_card_recycling: u64,
+    pub total_sol_spent: u64,
+    pub total_tokens_spent: u64,
+    pub staked_tokens: u64,
+    pub last_stake_slot: u64,
+    pub last_acc_sol_rewards_per_token: u128,
+    pub last_acc_token_rewards_per_token: u128,
+    pub claimed_token_rewards: u64,
+    pub boosters_without_pity: u8,
+}
+
+/// Serialized size of LegacyPlayerTail as allocated, i.e. with the largest
+/// pending_action and the 63 padding bytes after boosters_without_pity.
+const LEGACY_PLAYER_TAIL_SIZE: usize =
+    1 + 16 + 8 + 8 + 33 + 16 + 8 * 5 + 130 + 8 + 8 * 6 + 8 + 8 + 16 + 16 + 8 + 1 + 63;
+/// Size of a legacy Player account whose cards carry serials.
+pub const LEGACY_PLAYER_SIZE: usize =
+    8 + 32 + 10 + MAX_CARDS_PER_PLAYER as usize * CARD_SIZE + LEGACY_PLAYER_TAIL_SIZE;
+/// Size of a legacy Player account from before migrate_card_serials.
+pub const PRE_SERIAL_PLAYER_SIZE: usize =
+    8 + 32 + 10 + MAX_CARDS_PER_PLAYER as usize * LEGACY_CARD_SIZE + LEGACY_PLAYER_TAIL_SIZE;
+
+/// Checks that `data` is a legacy Player in exactly the layout
+/// migrate_player_to_zero_copy reads. Pre-serial accounts have to go through
+/// migrate_card_serials first, otherwise their 6-byte cards would be read as
+/// 14-byte ones.
+fn check_legacy_player(data: &[u8]) -> Result<()> {
+    require!(
+        data.len() != 8 + std::mem::size_of::<Player>(),
+        PonzimonError::AlreadyMigrated
+    );
+    require!(
+        data.len() != PRE_SERIAL_PLAYER_SIZE,
+        PonzimonError::CardSerialsNotMigrated
+    );
+    require!(
+        data.len() == LEGACY_PLAYER_SIZE && data[..8] == Player::DISCRIMINATOR[..],
+        PonzimonError::InvalidPlayerAccount
+    );
+    Ok(())
+}
+
+#[event]
+pub struct PlayerMigratedToZeroCopy {
+    pub player: Pubkey,
+    pub old_len: u64,
+    pub new_len: u64,
+}
+
+#[derive(Accounts)]
+pub struct MigratePlayerToZeroCopy<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    /// CHECK: Legacy Borsh layout can't be loaded through AccountLoader<Player>; the
+    /// owner, discriminator, size and stored owner key are checked manually.
+    #[account(
+        mut,
+        owner = crate::ID @ PonzimonError::Unauthorized,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: UncheckedAccount<'info>,
+    pub token_mint: Account<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Rewrites a Borsh-encoded Player into the zero-copy layout in place. The
+/// discriminator is shared by all layouts, so the account size tells them apart.
+pub fn migrate_player_to_zero_copy(ctx: Context<MigratePlayerToZeroCopy>) -> Result<()> {
+    let player_info = ctx.accounts.player.to_account_info();
+    let old_len = player_info.data_len();
+    let new_len = 8 + std::mem::size_of::<Player>();
+
+    // Copy the legacy bytes to the heap: the account is zeroed once resized
+    let legacy_data = {
+        let data = player_info.try_borrow_data()?;
+        check_legacy_player(&data)?;
+        data[8..].to_vec()
+    };
+    let reader = &mut &legacy_data[..];
+    let head =
+        LegacyPlayerHead::deserialize(reader).map_err(|_| PonzimonError::InvalidPlayerAccount)?;
+    require!(
+        head.owner == ctx.accounts.player_wallet.key(),
+        PonzimonError::Unauthorized
+    );
+
+    // Fund and resize the account for the zero-copy layout
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(player_info.lamports());
+    if rent_due > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.player_wallet.to_account_info(),
+                    to: player_info.clone(),
+                },
+            ),
+            rent_due,
+        )?;
+    }
+    player_info.realloc(new_len, false)?;
+    player_info.try_borrow_mut_data()?[8..].fill(0);
+
+    {
+        let mut data = player_info.try_borrow_mut_data()?;
+        let player: &mut Player = bytemuck::from_bytes_mut(&mut data[8..]);
+
+        player.owner = head.owner;
+        player.farm = Farm {
+            farm_type: head.farm.farm_type,
+            total_cards: head.farm.total_cards,
+            berry_capacity: head.farm.berry_capacity,
+        };
+        for card in player.cards.iter_mut() {
+            let legacy_card =
+                LegacyCard::deserialize(reader).map_err(|_| PonzimonError::InvalidPlayerAccount)?;
+            *card = Card {
+                id: legacy_card.id,
+                rarity: legacy_card.rarity,
+                hashpower: legacy_card.hashpower,
+                berry_consumption: legacy_card.berry_consumption,
+                serial: legacy_card.serial,
+            };
+        }
+        let legacy = LegacyPlayerTail::deserialize(reader)
+            .map_err(|_| PonzimonError::InvalidPlayerAccount)?;
+        player.card_count = legacy.card_count;
+        player.staked_cards_bitset = legacy.staked_cards_bitset;
+        player.berries = legacy.berries;
+        player.total_hashpower = legacy.total_hashpower;
+        player.set_referrer(legacy.referrer);
+        player.last_acc_tokens_per_hashpower = legacy.last_acc_tokens_per_hashpower;
+        player.last_claim_slot = legacy.last_claim_slot;
+        player.last_upgrade_slot = legacy.last_upgrade_slot;
+        player.total_rewards = legacy.total_rewards;
+        player.total_gambles = legacy.total_gambles;
+        player.total_gamble_wins = legacy.total_gamble_wins;
+        player.set_pending_action(legacy.pending_action);
+        player.commit_slot = legacy.commit_slot;
+        player.total_earnings_for_referrer = legacy.total_earnings_for_referrer;
+        player.total_booster_packs_opened = legacy.total_booster_packs_opened;
+        player.total_cards_recycled = legacy.total_cards_recycled;
+        player.successful_card_recycling = legacy.successful_card_recycling;
+        player.total_sol_spent = legacy.total_sol_spent;
+        player.total_tokens_spent = legacy.total_tokens_spent;
+        player.staked_tokens = legacy.staked_tokens;
+        player.last_stake_slot = legacy.last_stake_slot;
+        player.last_acc_sol_rewards_per_token = legacy.last_acc_sol_rewards_per_token;
+        player.last_acc_token_rewards_per_token = legacy.last_acc_token_rewards_per_token;
+        player.claimed_token_rewards = legacy.claimed_token_rewards;
+        player.boosters_without_pity = legacy.boosters_without_pity;
+    }
+
+    emit!(PlayerMigratedToZeroCopy {
+        player: player_info.key(),
+        old_len: old_len as u64,
+        new_len: new_len as u64,
+    });
+
+    Ok(())
+}
+
+/// RECYCLE CARDS (Secure two-step)
+
+#[derive(Accounts)]
+pub struct RecycleCardsCommit<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::RecycleAlreadyPending,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+pub fn recycle_cards_commit(
+    ctx: Context<RecycleCardsCommit>,
+    card_serials: Vec<u64>,
+) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    require!(
+        !card_serials.is_empty() && card_serials.len() <= 128,
+        PonzimonError::InvalidRecycleCardCount
+    );
+    let card_indices = card_serials
+        .iter()
+        .map(|&serial| find_card_by_serial(&player, serial))
+        .collect::<Result<Vec<u8>>>()?;
+    require!(
+        player.card_count as usize >= card_indices.len(),
+        PonzimonError::InvalidRecycleCardCount
+    );
+
+    // Validate card indices: must be unique, valid, and not staked
+    let mut sorted_indices = card_indices.clone();
+    sorted_indices.sort();
+    for i in 1..sorted_indices.len() {
+        require!(
+            sorted_indices[i] != sorted_indices[i - 1],
+            PonzimonError::DuplicateRecycleCardIndices
+        );
+    }
+    for &index in &card_indices {
+        validate_card_index(index, player.card_count as usize)?;
+        require!(!player.is_card_staked(index), PonzimonError::CardIsStaked);
+        require!(!player.is_card_locked(index), PonzimonError::CardLocked);
+    }
+
+    // Create array from vector (pad with 0s if needed)
+    let mut card_indices_array = [0u8; 128];
+    for (i, &index) in card_indices.iter().enumerate() {
+        card_indices_array[i] = index;
+    }
+
+    // Set pending state with card indices
+    player.set_pending_action(PendingRandomAction::Recycle {
+        card_indices: card_indices_array,
+        card_count: card_indices.len() as u8,
+    });
+    player.commit_slot = slot;
+
+    // Update recycling attempt tracking
+    gs.total_card_recycling_attempts = gs.total_card_recycling_attempts.saturating_add(1);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RecycleCardsSettle<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = matches!(player.load()?.pending_action(), PendingRandomAction::Recycle { .. }) @ PonzimonError::NoRecyclePending,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+    /// CHECK: Checked manually, otherwise it exceeds CU
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+pub fn recycle_cards_settle(ctx: Context<RecycleCardsSettle>) -> Result<()> {
+    let clock: Clock = Clock::get()?;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+    let catalog = ctx.accounts.card_catalog.load()?;
+
+    require!(
+        clock.slot >= player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS,
+        PonzimonError::RandomnessNotResolved
+    );
+    let reveal_slot = player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS;
+
+    let random_value = reveal_hash(&ctx.accounts.slot_hashes, reveal_slot)?;
+
+    // Settle rewards before changing player state
+    update_pool(gs, clock.slot);
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+
+    // Extract recycled card data from pending action
+    let (card_indices_array, card_count) = if let PendingRandomAction::Recycle {
+        card_indices,
+        card_count,
+    } = player.pending_action()
+    {
+        (card_indices, card_count)
+    } else {
+        return Err(PonzimonError::NoRecyclePending.into());
+    };
+
+    let mut successful_upgrades = 0u8;
+    let upgrade_odds_bps = gs.recycle_upgrade_odds();
+    let mut new_cards: Vec<Card> = Vec::new(); // Store new cards to add
+
+    // Process each card individually with the configured chance for upgrade
+    for i in 0..card_count {
+        let card_index = card_indices_array[i as usize];
+
+        // Validate card index is still valid
+        if (card_index as usize) >= (player.card_count as usize) {
+            continue; // Skip invalid indices
+        }
+
+        let card = &player.cards[card_index as usize];
+        let current_rarity = card.rarity;
+
+        // Use different slice of the random value for each card
+        let random_bps = ponzimon_math::recycle_roll_bps(&random_value, i as usize);
+
+        // Upgrade to next rarity with the configured per-rarity odds
+        if ponzimon_math::recycle_upgrades(&upgrade_odds_bps, current_rarity, random_bps) {
+            if let Some(next_rarity) = get_next_rarity(current_rarity) {
+                // Find a random active catalog card of the next rarity
+                let cards_of_next_rarity = catalog.active_of_rarity(next_rarity);
+
+                if !cards_of_next_rarity.is_empty() {
+                    // Use additional randomness for card selection
+                    let mut random_bytes: [u8; 4] = [0; 4];
+                    let start_idx = (i as usize * 4) % (random_value.len() - 3);
+                    random_bytes.copy_from_slice(&random_value[start_idx..start_idx + 4]);
+                    let random_u32 = u32::from_le_bytes(random_bytes);
+
+                    let card_index_in_rarity = (random_u32 as usize) % cards_of_next_rarity.len();
+
+                    // Store the new card data to add after removing old cards
+                    new_cards.push(mint_card(
+                        gs,
+                        cards_of_next_rarity[card_index_in_rarity].to_card(),
+                    ));
+                    successful_upgrades += 1;
+                }
+            }
+        }
+        // Otherwise: card is lost (no new card generated)
+    }
+
+    let indices_to_remove: Vec<u8> = card_indices_array[0..card_count as usize].to_vec();
+    player.batch_remove_cards(&indices_to_remove)?;
+
+    // Add the new upgraded cards
+    for new_card in new_cards {
+        require!(
+            (player.card_count as usize) < MAX_CARDS_PER_PLAYER as usize,
+            PonzimonError::MachineCapacityExceeded
+        );
+
+        player.add_card(new_card)?;
+    }
+
+    // Reset recycle state
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+
+    // Update tracking statistics
+    player.total_cards_recycled = player
+        .total_cards_recycled
+        .saturating_add(card_count as u64);
+
+    if successful_upgrades > 0 {
+        player.successful_card_recycling = player
+            .successful_card_recycling
+            .saturating_add(successful_upgrades as u64);
+        gs.total_successful_card_recycling = gs
+            .total_successful_card_recycling
+            .saturating_add(successful_upgrades as u64);
+    }
+
+    emit!(CardsRecycled {
+        player: player_key,
+        successful_upgrades,
+        total_recycled: card_count,
+        upgrade_odds_bps,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  FUSE CARDS (Sacrifice-weighted recycling, secure two-step)
+/// ────────────────────────────────────────────────────────────────────────────
+/// Hard cap on the fusion success chance so a single roll is never a sure thing.
+pub const MAX_FUSION_ODDS_BPS: u64 = 9_500;
+
+#[event]
+pub struct CardsFused {
+    pub player: Pubkey,
+    pub source_rarity: u8,
+    pub cards_sacrificed: u8,
+    pub hashpower_sacrificed: u64,
+    pub success_odds_bps: u16,
+    pub new_card_id: Option<u16>, // None if the fusion failed
+}
+
+/// Success chance for fusing `card_count` cards of `rarity` with a combined
+/// `hashpower_sacrificed`. Each average-hashpower card contributes the regular
+/// per-card recycle odds, so fusing N average cards matches the expected upgrades
+/// of recycling them one by one, concentrated into a single roll; stronger cards
+/// weigh more.
+fn fusion_odds_bps(
+    gs: &GlobalState,
+    catalog: &CardCatalog,
+    rarity: u8,
+    hashpower_sacrificed: u64,
+) -> u64 {
+    let base_odds_bps = ponzimon_math::upgrade_odds_bps(&gs.recycle_upgrade_odds(), rarity);
+
+    let cards_of_rarity = catalog.active_of_rarity(rarity);
+    let total_catalog_hashpower: u64 = cards_of_rarity
+        .iter()
+        .map(|entry| entry.hashpower as u64)
+        .sum();
+    if cards_of_rarity.is_empty() || total_catalog_hashpower == 0 {
+        return 0;
+    }
+
+    // odds = base * sacrificed / average, with average = total / len
+    base_odds_bps
+        .saturating_mul(hashpower_sacrificed)
+        .saturating_mul(cards_of_rarity.len() as u64)
+        .checked_div(total_catalog_hashpower)
+        .unwrap_or(0)
+        .min(MAX_FUSION_ODDS_BPS)
+}
+
+#[derive(Accounts)]
+pub struct FuseCardsCommit<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::RecycleAlreadyPending,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+pub fn fuse_cards_commit(ctx: Context<FuseCardsCommit>, card_serials: Vec<u64>) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+    require!(
+        card_serials.len() >= 2 && card_serials.len() <= 128,
+        PonzimonError::InvalidRecycleCardCount
+    );
+    let card_indices = card_serials
+        .iter()
+        .map(|&serial| find_card_by_serial(&player, serial))
+        .collect::<Result<Vec<u8>>>()?;
+    require!(
+        player.card_count as usize >= card_indices.len(),
+        PonzimonError::InvalidRecycleCardCount
+    );
+
+    // Validate card indices: must be unique, valid, and not staked
+    let mut sorted_indices = card_indices.clone();
+    sorted_indices.sort();
+    for i in 1..sorted_indices.len() {
+        require!(
+            sorted_indices[i] != sorted_indices[i - 1],
+            PonzimonError::DuplicateRecycleCardIndices
+        );
+    }
+    for &index in &card_indices {
+        validate_card_index(index, player.card_count as usize)?;
+        require!(!player.is_card_staked(index), PonzimonError::CardIsStaked);
+        require!(!player.is_card_locked(index), PonzimonError::CardLocked);
+    }
+
+    // All sacrificed cards must share a rarity that can still be upgraded
+    let source_rarity = player.cards[card_indices[0] as usize].rarity;
+    require!(
+        get_next_rarity(source_rarity).is_some(),
+        PonzimonError::InvalidFusionRarity
+    );
+    for &index in &card_indices {
+        require!(
+            player.cards[index as usize].rarity == source_rarity,
+            PonzimonError::FusionRarityMismatch
+        );
+    }
+
+    // Create array from vector (pad with 0s if needed)
+    let mut card_indices_array = [0u8; 128];
+    for (i, &index) in card_indices.iter().enumerate() {
+        card_indices_array[i] = index;
+    }
+
+    // Set pending state with card indices
+    player.set_pending_action(PendingRandomAction::Fusion {
+        card_indices: card_indices_array,
+        card_count: card_indices.len() as u8,
+    });
+    player.commit_slot = slot;
+
+    // Update recycling attempt tracking
+    gs.total_card_recycling_attempts = gs.total_card_recycling_attempts.saturating_add(1);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct FuseCardsSettle<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = matches!(player.load()?.pending_action(), PendingRandomAction::Fusion { .. }) @ PonzimonError::NoRecyclePending,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+    /// CHECK: Checked manually, otherwise it exceeds CU
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+pub fn fuse_cards_settle(ctx: Context<FuseCardsSettle>) -> Result<()> {
+    let clock: Clock = Clock::get()?;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+    let catalog = ctx.accounts.card_catalog.load()?;
+
+    require!(
+        clock.slot >= player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS,
+        PonzimonError::RandomnessNotResolved
+    );
+    let reveal_slot = player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS;
+
+    let random_value = reveal_hash(&ctx.accounts.slot_hashes, reveal_slot)?;
+
+    // Settle rewards before changing player state
+    update_pool(gs, clock.slot);
+    player.last_acc_tokens_per_hashpower = gs.acc_tokens_per_hashpower;
+
+    let (card_indices_array, card_count) = if let PendingRandomAction::Fusion {
+        card_indices,
+        card_count,
+    } = player.pending_action()
+    {
+        (card_indices, card_count)
+    } else {
+        return Err(PonzimonError::NoRecyclePending.into());
+    };
+    let indices = &card_indices_array[0..card_count as usize];
+
+    // Cards can't be staked or moved while pending, so commit-time checks still hold
+    let source_rarity = player.cards[indices[0] as usize].rarity;
+    let hashpower_sacrificed: u64 = indices
+        .iter()
+        .map(|&index| player.cards[index as usize].hashpower as u64)
+        .sum();
+    let success_odds_bps = fusion_odds_bps(gs, &catalog, source_rarity, hashpower_sacrificed);
+
+    // Maps the full u16 range onto 0..10_000 without bias towards low values
+    let random_bps = ponzimon_math::recycle_roll_bps(&random_value, 0);
+
+    let mut new_card: Option<Card> = None;
+    if random_bps < success_odds_bps {
+        if let Some(next_rarity) = get_next_rarity(source_rarity) {
+            let cards_of_next_rarity = catalog.active_of_rarity(next_rarity);
+            if !cards_of_next_rarity.is_empty() {
+                let mut random_bytes: [u8; 4] = [0; 4];
+                random_bytes.copy_from_slice(&random_value[2..6]);
+                let random_u32 = u32::from_le_bytes(random_bytes);
+
+                let card_index_in_rarity =
+                    ponzimon_math::pick_index(random_u32, cards_of_next_rarity.len());
+                new_card = Some(mint_card(
+                    gs,
+                    cards_of_next_rarity[card_index_in_rarity].to_card(),
+                ));
+            }
+        }
+    }
+
+    // Every sacrificed card is consumed, win or lose
+    player.batch_remove_cards(indices)?;
+
+    if let Some(card) = new_card {
+        player.add_card(card)?;
+    }
+
+    // Reset fusion state
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+
+    // Update tracking statistics
+    player.total_cards_recycled = player
+        .total_cards_recycled
+        .saturating_add(card_count as u64);
+
+    if new_card.is_some() {
+        player.successful_card_recycling = player.successful_card_recycling.saturating_add(1);
+        gs.total_successful_card_recycling = gs.total_successful_card_recycling.saturating_add(1);
+    }
+
+    emit!(CardsFused {
+        player: player_key,
+        source_rarity,
+        cards_sacrificed: card_count,
+        hashpower_sacrificed,
+        success_odds_bps: success_odds_bps as u16,
+        new_card_id: new_card.map(|card| card.id),
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  CRAFT (spend discard shards on a booster or a card of a chosen rarity)
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CraftTarget {
+    /// A regular 5-card pack, settled by settle_open_booster
+    Booster,
+    /// One random active catalog card of this rarity, settled by craft_card_settle
+    Card { rarity: u8 },
+}
+
+#[event]
+pub struct ShardsSpent {
+    pub player: Pubkey,
+    pub target: CraftTarget,
+    pub shards_spent: u64,
+}
+
+#[event]
+pub struct CardCrafted {
+    pub player: Pubkey,
+    pub card_serial: u64,
+    pub card_id: u16,
+    pub rarity: u8,
+}
+
+#[derive(Accounts)]
+pub struct CraftCommit<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::PendingActionInProgress,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+pub fn craft_commit(ctx: Context<CraftCommit>, target: CraftTarget) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+
+    let (cost, new_cards, pending_action) = match target {
+        CraftTarget::Booster => (
+            gs.craft_booster_shards as u64,
+            5,
+            PendingRandomAction::Booster,
+        ),
+        CraftTarget::Card { rarity } => {
+            require!(
+                (rarity as usize) < RARITY_COUNT,
+                PonzimonError::InvalidCraftTarget
+            );
+            // Checked here so shards aren't spent on a rarity that can't be drawn
+            require!(
+                !ctx.accounts
+                    .card_catalog
+                    .load()?
+                    .active_of_rarity(rarity)
+                    .is_empty(),
+                PonzimonError::InvalidCraftTarget
+            );
+            let cost = gs.craft_card_shards[rarity as usize] as u64;
+            (cost, 1, PendingRandomAction::Craft { rarity, cost })
+        }
+    };
+    require!(cost > 0, PonzimonError::CraftingDisabled);
+    require!(player.shards >= cost, PonzimonError::InsufficientShards);
+    require!(
+        (player.card_count as usize) + new_cards <= MAX_CARDS_PER_PLAYER as usize,
+        PonzimonError::MachineCapacityExceeded
+    );
+
+    player.shards -= cost;
+    player.set_pending_action(pending_action);
+    player.commit_slot = slot;
+    player.auto_stake_booster = 0;
+
+    emit!(ShardsSpent {
+        player: player_key,
+        target,
+        shards_spent: cost,
+    });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CraftCardSettle<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = matches!(player.load()?.pending_action(), PendingRandomAction::Craft { .. }) @ PonzimonError::NoPendingAction,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        seeds = [CARD_CATALOG_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub card_catalog: AccountLoader<'info, CardCatalog>,
+    pub token_mint: Account<'info, Mint>,
+    /// CHECK: Checked manually, otherwise it exceeds CU
+    pub slot_hashes: AccountInfo<'info>,
+}
+
+pub fn craft_card_settle(ctx: Context<CraftCardSettle>) -> Result<()> {
+    let clock: Clock = Clock::get()?;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(
+        clock.slot >= player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS,
+        PonzimonError::RandomnessNotResolved
+    );
+    let reveal_slot = player.commit_slot + MIN_RANDOMNESS_DELAY_SLOTS;
+
+    let random_value = reveal_hash(&ctx.accounts.slot_hashes, reveal_slot)?;
+
+    let (rarity, cost) =
+        if let PendingRandomAction::Craft { rarity, cost } = player.pending_action() {
+            (rarity, cost)
+        } else {
+            return Err(PonzimonError::NoPendingAction.into());
+        };
+
+    let mut random_bytes: [u8; 4] = [0; 4];
+    random_bytes.copy_from_slice(&random_value[0..4]);
+    let random_u32 = u32::from_le_bytes(random_bytes);
+
+    // The rarity was checked at commit, but the authority may have retired its
+    // last card since; refund exactly what was paid in that case, even if the
+    // price has been changed in between.
+    let catalog = ctx.accounts.card_catalog.load()?;
+    let cards_of_rarity = catalog.active_of_rarity(rarity);
+    if cards_of_rarity.is_empty() {
+        player.shards = player.shards.saturating_add(cost);
+    } else {
+        let entry = cards_of_rarity[ponzimon_math::pick_index(random_u32, cards_of_rarity.len())];
+        let card = mint_card(gs, entry.to_card());
+        player.add_card(card)?;
+
+        emit!(CardCrafted {
+            player: player_key,
+            card_serial: card.serial,
+            card_id: entry.id,
+            rarity,
+        });
+    }
+
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  BUYBACK (the protocol buys unwanted cards back for tokens)
+/// ────────────────────────────────────────────────────────────────────────────
+pub const BUYBACK_CONFIG_SEED: &[u8] = b"buyback_config";
+pub const BUYBACK_VAULT_SEED: &[u8] = b"buyback_vault";
+
+#[account]
+pub struct BuybackConfig {
+    /// Tokens paid per card, indexed by rarity; 0 means that rarity isn't bought
+    pub prices: [u64; RARITY_COUNT],
+    /// Percentage of the non-burned booster fees routed into the buyback vault
+    pub fee_share: u8,
+    pub total_cards_bought: u64,
+    pub total_tokens_paid: u64,
+}
+
+impl BuybackConfig {
+    pub const SIZE: usize = 8 /* discriminator */
+        + 8 * RARITY_COUNT /* prices */
+        + 1 /* fee_share */
+        + 8 + 8; /* total_cards_bought + total_tokens_paid */
+}
+
+#[event]
+pub struct BuybackUpdated {
+    pub prices: [u64; RARITY_COUNT],
+    pub fee_share: u8,
+}
+
+#[event]
+pub struct CardSoldToProtocol {
+    pub player: Pubkey,
+    pub card_serial: u64,
+    pub card_id: u16,
+    pub rarity: u8,
+    pub price: u64,
+}
+
+#[derive(Accounts)]
+pub struct InitializeBuyback<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        init,
+        payer = authority,
+        space = BuybackConfig::SIZE,
+        seeds = [BUYBACK_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub buyback_config: Account<'info, BuybackConfig>,
+    #[account(
+        init,
+        payer = authority,
+        token::mint = token_mint,
+        token::authority = global_state,
+        seeds = [BUYBACK_VAULT_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub buyback_vault: Account<'info, TokenAccount>,
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Creates the buyback config and vault. Buyback starts disabled: every price
+/// and the fee share are 0 until the authority sets them.
+pub fn initialize_buyback(ctx: Context<InitializeBuyback>) -> Result<()> {
+    let config = &mut ctx.accounts.buyback_config;
+    config.prices = [0; RARITY_COUNT];
+    config.fee_share = 0;
+    config.total_cards_bought = 0;
+    config.total_tokens_paid = 0;
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateBuyback<'info> {
+    pub authority: Signer<'info>,
+    #[account(
+        has_one = authority @ PonzimonError::Unauthorized,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [BUYBACK_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub buyback_config: Account<'info, BuybackConfig>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+pub fn update_buyback(
+    ctx: Context<UpdateBuyback>,
+    prices: [u64; RARITY_COUNT],
+    fee_share: u8,
+) -> Result<()> {
+    require!(fee_share <= 100, PonzimonError::InvalidBuybackShare);
+
+    let config = &mut ctx.accounts.buyback_config;
+    config.prices = prices;
+    config.fee_share = fee_share;
+
+    emit!(BuybackUpdated { prices, fee_share });
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(card_serial: u64)]
+pub struct SellCardToProtocol<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() == PendingRandomAction::None @ PonzimonError::PendingActionInProgress,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [BUYBACK_CONFIG_SEED, token_mint.key().as_ref()],
+        bump
+    )]
+    pub buyback_config: Account<'info, BuybackConfig>,
+    #[account(
+        mut,
+        seeds = [BUYBACK_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub buyback_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = player_token_account.mint == global_state.token_mint,
+        constraint = player_token_account.owner == player_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub player_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Removes one unstaked card and pays its rarity's buyback price from the
+/// buyback vault.
+pub fn sell_card_to_protocol(ctx: Context<SellCardToProtocol>, card_serial: u64) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    let player_key = ctx.accounts.player.key();
+    let mut player = ctx.accounts.player.load_mut()?;
+    let gs = &mut ctx.accounts.global_state;
+
+    require!(gs.production_enabled, PonzimonError::ProductionDisabled);
+
+    settle_and_mint_rewards(
+        &mut player,
+        gs,
+        slot,
+        &ctx.accounts.player_token_account.to_account_info(),
+        &ctx.accounts.token_mint.to_account_info(),
+        &ctx.accounts.rewards_vault.to_account_info(),
+        &ctx.accounts.token_program.to_account_info(),
+        ctx.bumps.global_state,
+    )?;
+
+    let card_index = find_card_by_serial(&player, card_serial)?;
+    validate_card_index(card_index, player.card_count as usize)?;
+    require!(
+        !player.is_card_staked(card_index),
+        PonzimonError::CardIsStaked
+    );
+    require!(
+        !player.is_card_being_recycled(card_index),
+        PonzimonError::CardIsStaked // Reusing this error for consistency
+    );
+    require!(
+        !player.is_card_locked(card_index),
+        PonzimonError::CardLocked
+    );
+
+    let card = player.cards[card_index as usize];
+    let config = &mut ctx.accounts.buyback_config;
+    let price = config
+        .prices
+        .get(card.rarity as usize)
+        .copied()
+        .unwrap_or(0);
+    require!(price > 0, PonzimonError::BuybackUnavailable);
+    require!(
+        ctx.accounts.buyback_vault.amount >= price,
+        PonzimonError::InsufficientBuybackFunds
+    );
+
+    // Effects before the transfer
+    remove_cards(&mut player, &[card_index])?;
+    config.total_cards_bought = config.total_cards_bought.saturating_add(1);
+    config.total_tokens_paid = config.total_tokens_paid.saturating_add(price);
+
+    let token_mint_key = ctx.accounts.token_mint.key();
+    let seeds = &[
+        GLOBAL_STATE_SEED,
+        token_mint_key.as_ref(),
+        &[ctx.bumps.global_state],
+    ];
+    let signer = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.buyback_vault.to_account_info(),
+                to: ctx.accounts.player_token_account.to_account_info(),
+                authority: gs.to_account_info(),
+            },
+            signer,
+        ),
+        price,
+    )?;
+
+    emit!(CardSoldToProtocol {
+        player: player_key,
+        card_serial,
+        card_id: card.id,
+        rarity: card.rarity,
+        price,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  REFERRAL VAULT (per-referrer escrow for referral commissions)
+/// ────────────────────────────────────────────────────────────────────────────
+pub const REFERRAL_VAULT_SEED: &[u8] = b"referral_vault";
+
+#[event]
+pub struct ReferralRewardsClaimed {
+    pub referrer: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralRewards<'info> {
+    #[account(mut)]
+    pub referrer_wallet: Signer<'info>,
+    #[account(
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REFERRAL_VAULT_SEED, token_mint.key().as_ref(), referrer_wallet.key().as_ref()],
+        bump,
+    )]
+    pub referral_vault: Box<Account<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = referrer_token_account.mint == global_state.token_mint,
+        constraint = referrer_token_account.owner == referrer_wallet.key() @ PonzimonError::InvalidTokenAccountOwner
+    )]
+    pub referrer_token_account: Box<Account<'info, TokenAccount>>,
+    #[account(
+        constraint = token_mint.key() == global_state.token_mint @ PonzimonError::InvalidTokenMint
+    )]
+    pub token_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+/// Pays out every commission escrowed in the referrer's vault. The vault is
+/// created by the first referred player who pays a commission.
+pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+    let amount = ctx.accounts.referral_vault.amount;
+    require!(amount > 0, PonzimonError::NoReferralRewards);
+
+    let token_mint_key = ctx.accounts.token_mint.key();
+    let seeds = &[
+        GLOBAL_STATE_SEED,
+        token_mint_key.as_ref(),
+        &[ctx.bumps.global_state],
+    ];
+    let signer = &[&seeds[..]];
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.referral_vault.to_account_info(),
+                to: ctx.accounts.referrer_token_account.to_account_info(),
+                authority: ctx.accounts.global_state.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+    )?;
+
+    emit!(ReferralRewardsClaimed {
+        referrer: ctx.accounts.referrer_wallet.key(),
+        amount,
+    });
+
+    Ok(())
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+///  CANCEL PENDING ACTION
+/// ────────────────────────────────────────────────────────────────────────────
+#[derive(Accounts)]
+pub struct CancelPendingAction<'info> {
+    #[account(mut)]
+    pub player_wallet: Signer<'info>,
+    #[account(
+        mut,
+        constraint = player.load()?.owner == player_wallet.key() @ PonzimonError::Unauthorized,
+        constraint = player.load()?.pending_action() != PendingRandomAction::None @ PonzimonError::NoPendingAction,
+        seeds = [PLAYER_SEED, player_wallet.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub player: AccountLoader<'info, Player>,
+    #[account(
+        mut,
+        seeds = [GLOBAL_STATE_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [REWARDS_VAULT_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub rewards_vault: Account<'info, TokenAccount>,
+    pub token_mint: Account<'info, Mint>,
+}
+
+pub fn cancel_pending_action(ctx: Context<CancelPendingAction>) -> Result<()> {
+    let mut player = ctx.accounts.player.load_mut()?;
+    let clock = Clock::get()?;
+
+    require!(
+        clock.slot > player.commit_slot + CANCEL_TIMEOUT_SLOTS,
+        PonzimonError::CancelTimeoutNotExpired
+    );
+
+    if let PendingRandomAction::Recycle {
+        card_indices,
+        card_count,
+    }
+    | PendingRandomAction::Fusion {
+        card_indices,
+        card_count,
+    } = player.pending_action()
+    {
+        let mut indices_to_remove: Vec<u8> = card_indices[0..card_count as usize].to_vec();
+        indices_to_remove.sort_by(|a, b| b.cmp(a)); // Sort descending
+
+        // Batch remove cards
+        player.batch_remove_cards(&indices_to_remove)?;
+    }
+
+    player.set_pending_action(PendingRandomAction::None);
+    player.commit_slot = 0;
+    player.auto_stake_booster = 0;
+
+    Ok(())
+}
+
+/// Helper function to handle fee transfers with referral logic. `referral_fee`
+/// is the percentage of the non-burned fees owed to the player's referrer for
+/// this kind of action; 0 sends everything to the protocol.
+#[allow(clippy::too_many_arguments)]
+fn handle_fee_transfers<'info>(
+    player: &mut Player,
+    gs: &mut Account<'info, GlobalState>,
+    total_amount: u64,
+    player_token_account: &AccountInfo<'info>,
+    fees_token_account: &AccountInfo<'info>,
+    referral_vault: Option<AccountInfo<'info>>,
+    buyback: Option<(&AccountInfo<'info>, u8)>,
+    player_wallet: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_mint: &AccountInfo<'info>,
+    referral_fee: u8,
+) -> Result<()> {
+    // Calculate burn and fees amounts
+    let (burn_amount, mut fees_amount) = split_burn(total_amount, gs.burn_rate);
+
+    // Burn tokens
+    if burn_amount > 0 {
+        gs.burned_tokens = gs.burned_tokens.saturating_add(burn_amount);
+        token::burn(
+            CpiContext::new(
+                token_program.clone(),
+                Burn {
+                    mint: token_mint.clone(),
+                    from: player_token_account.clone(),
+                    authority: player_wallet.clone(),
+                },
+            ),
+            burn_amount,
+        )?;
+    }
+
+    // Fund the buyback vault (boosters only); referral and protocol split the rest
+    if let Some((buyback_vault, fee_share)) = buyback {
+        let (buyback_amount, remaining_fees) = split_buyback(fees_amount, fee_share);
+        if buyback_amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    token_program.clone(),
+                    Transfer {
+                        from: player_token_account.clone(),
+                        to: buyback_vault.clone(),
+                        authority: player_wallet.clone(),
+                    },
+                ),
+                buyback_amount,
+            )?;
+        }
+        fees_amount = remaining_fees;
+    }
+
+    // Handle referral and protocol fees
+    if referral_fee > 0 && player.referrer().is_some() {
+        // The accounts struct ties the vault to the player's referrer
+        let referral_vault = referral_vault.ok_or(PonzimonError::ReferrerAccountMissing)?;
+        let (referral_commission, protocol_fee) = split_referral(fees_amount, referral_fee);
+
+        // Escrow the commission; the referrer withdraws it with claim_referral_rewards
+        if referral_commission > 0 {
+            token::transfer(
+                CpiContext::new(
+                    token_program.clone(),
+                    Transfer {
+                        from: player_token_account.clone(),
+                        to: referral_vault,
+                        authority: player_wallet.clone(),
+                    },
+                ),
+                referral_commission,
+            )?;
+            player.total_earnings_for_referrer = player
+                .total_earnings_for_referrer
+                .saturating_add(referral_commission);
+        }
+
+        // Transfer the remaining fee to the protocol wallet.
+        if protocol_fee > 0 {
+            token::transfer(
+                CpiContext::new(
+                    token_program.clone(),
+                    Transfer {
+                        from: player_token_account.clone(),
+                        to: fees_token_account.clone(),
+                        authority: player_wallet.clone(),
+                    },
+                ),
+                protocol_fee,
+            )?;
+        }
+        return Ok(());
+    }
+    // No referrer or no commission for this action, so the entire fee amount goes to the protocol.
+    if fees_amount > 0 {
+        token::transfer(
+            CpiContext::new(
+                token_program.clone(),
+                Transfer {
+                    from: player_token_account.clone(),
+                    to: fees_token_account.clone(),
+                    authority: player_wallet.clone(),
+                },
+            ),
+            fees_amount,
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    fn player_buffer(len: usize) -> Vec<u8> {
+        let mut data = vec![0u8; len];
+        data[..8].copy_from_slice(&Player::DISCRIMINATOR);
+        data
+    }
+
+    #[test]
+    fn batch_claim_inside_cooldown_fails() {
+        let mut player = Player::zeroed();
+        player.last_claim_slot = 1_000;
+        let gs = GlobalState {
+            claim_cooldown_slots: 500,
+            ..GlobalState::default()
+        };
+        let ready_slot = claim_ready_slot(&player, &gs);
+
+        assert_eq!(
+            check_claim_cooldown(ready_slot, &gs, 1_499).unwrap_err(),
+            PonzimonError::ClaimCooldownActive.into()
+        );
+        assert!(check_claim_cooldown(ready_slot, &gs, 1_500).is_ok());
+    }
+
+    #[test]
+    fn legacy_player_sizes() {
+        assert_eq!(PRE_SERIAL_PLAYER_SIZE, 1246);
+        assert_eq!(LEGACY_PLAYER_SIZE, 2270);
+        assert!(check_legacy_player(&player_buffer(LEGACY_PLAYER_SIZE)).is_ok());
+    }
+
+    #[test]
+    fn migrate_rejects_pre_serial_player() {
+        let err = check_legacy_player(&player_buffer(PRE_SERIAL_PLAYER_SIZE)).unwrap_err();
+        assert_eq!(err, PonzimonError::CardSerialsNotMigrated.into());
+    }
+
+    #[test]
+    fn migrate_rejects_other_sizes() {
+        let migrated = player_buffer(8 + std::mem::size_of::<Player>());
+        assert_eq!(
+            check_legacy_player(&migrated).unwrap_err(),
+            PonzimonError::AlreadyMigrated.into()
+        );
+        for len in [LEGACY_PLAYER_SIZE - 1, LEGACY_PLAYER_SIZE + 2] {
+            assert_eq!(
+                check_legacy_player(&player_buffer(len)).unwrap_err(),
+                PonzimonError::InvalidPlayerAccount.into()
+            );
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use anchor_lang::prelude::*;
+
+pub mod constants;
+pub mod errors;
+pub mod helpers;
+pub mod instructions;
+pub mod state;
+
+use instructions::*;
+
+#[cfg(not(feature = "devnet"))]
+declare_id!("pmnSxWFQUz7nCncGznUYhaJdJFFUvcx97GZFhbiCYWi");
+#[cfg(feature = "devnet")]
+declare_id!("pv5gAmRb1GZ92k7iuLe5JdNmj5R8Ch61N4beuf2yEdK");
+
+#[program]
+pub mod ponzimon {
+    use super::*;
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_program(
+        ctx: Context<InitializeProgram>,
+        start_slot: u64,
+        total_supply: u64,
+        reward_rate: u64,
+        initial_farm_purchase_fee_lamports: Option<u64>,
+        booster_pack_cost_microtokens: Option<u64>,
+        gamble_fee_lamports: Option<u64>,
+        staking_lockup_slots: u64,
+        token_reward_rate: u64,
+    ) -> Result<()> {
+        instructions::initialize_program(
+            ctx,
+            start_slot,
+            total_supply,
+            reward_rate,
+            initial_farm_purchase_fee_lamports,
+            booster_pack_cost_microtokens,
+            gamble_fee_lamports,
+            staking_lockup_slots,
+            token_reward_rate,
+        )
+    }
+
+    pub fn purchase_initial_farm(ctx: Context<PurchaseInitialFarm>) -> Result<()> {
+        instructions::purchase_initial_farm(ctx)
+    }
+
+    pub fn discard_card(ctx: Context<DiscardCard>, card_serial: u64) -> Result<()> {
+        instructions::discard_card(ctx, card_serial)
+    }
+
+    pub fn discard_cards(ctx: Context<DiscardCards>, card_serials: Vec<u64>) -> Result<()> {
+        instructions::discard_cards(ctx, card_serials)
+    }
+
+    pub fn lock_cards(ctx: Context<LockCards>, card_serials: Vec<u64>) -> Result<()> {
+        instructions::lock_cards(ctx, card_serials)
+    }
+
+    pub fn unlock_cards(ctx: Context<LockCards>, card_serials: Vec<u64>) -> Result<()> {
+        instructions::unlock_cards(ctx, card_serials)
+    }
+
+    pub fn stake_card(ctx: Context<StakeCard>, card_serial: u64) -> Result<()> {
+        instructions::stake_card(ctx, card_serial)
+    }
+
+    pub fn unstake_card(ctx: Context<UnstakeCard>, card_serial: u64) -> Result<()> {
+        instructions::unstake_card(ctx, card_serial)
+    }
+
+    pub fn upgrade_farm(ctx: Context<UpgradeFarm>, farm_type: u8) -> Result<()> {
+        instructions::upgrade_farm(ctx, farm_type)
+    }
+
+    pub fn upgrade_farm_to(ctx: Context<UpgradeFarm>, target_farm_type: u8) -> Result<()> {
+        instructions::upgrade_farm_to(ctx, target_farm_type)
+    }
+
+    pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+        instructions::claim_rewards(ctx)
+    }
+
+    pub fn execute_actions(ctx: Context<ExecuteActions>, actions: Vec<PlayerAction>) -> Result<()> {
+        instructions::execute_actions(ctx, actions)
+    }
+
+    pub fn optimize_loadout(ctx: Context<OptimizeLoadout>) -> Result<()> {
+        instructions::optimize_loadout(ctx)
+    }
+
+    pub fn open_booster_commit(ctx: Context<OpenBoosterCommit>, auto_stake: bool) -> Result<()> {
+        instructions::open_booster_commit(ctx, auto_stake)
+    }
+
+    pub fn settle_open_booster(ctx: Context<SettleOpenBooster>) -> Result<()> {
+        instructions::settle_open_booster(ctx)
+    }
+
+    pub fn get_pending_rewards(ctx: Context<PlayerView>, owner: Pubkey) -> Result<()> {
+        instructions::get_pending_rewards(ctx, owner)
+    }
+
+    pub fn get_player_summary(ctx: Context<PlayerView>, owner: Pubkey) -> Result<()> {
+        instructions::get_player_summary(ctx, owner)
+    }
+
+    pub fn get_global_stats(ctx: Context<GlobalView>) -> Result<()> {
+        instructions::get_global_stats(ctx)
+    }
+
+    pub fn toggle_production(ctx: Context<ToggleProduction>, enable: bool) -> Result<()> {
+        instructions::toggle_production(ctx, enable)
+    }
+
+    pub fn update_parameter(
+        ctx: Context<UpdateParameters>,
+        parameter_index: u8,
+        parameter_value: u64,
+    ) -> Result<()> {
+        instructions::update_parameter(ctx, parameter_index, parameter_value)
+    }
+
+    pub fn update_pool_manual(ctx: Context<UpdatePool>) -> Result<()> {
+        instructions::update_pool_manual(ctx)
+    }
+
+    pub fn reset_player(ctx: Context<ResetPlayer>) -> Result<()> {
+        instructions::reset_player(ctx)
+    }
+
+    pub fn initialize_farm_config(ctx: Context<InitializeFarmConfig>) -> Result<()> {
+        instructions::initialize_farm_config(ctx)
+    }
+
+    pub fn update_farm_config(ctx: Context<UpdateFarmConfig>, tiers: Vec<FarmTier>) -> Result<()> {
+        instructions::update_farm_config(ctx, tiers)
+    }
+
+    pub fn initialize_card_catalog(ctx: Context<InitializeCardCatalog>) -> Result<()> {
+        instructions::initialize_card_catalog(ctx)
+    }
+
+    pub fn add_catalog_cards(
+        ctx: Context<UpdateCardCatalog>,
+        entries: Vec<CatalogEntry>,
+    ) -> Result<()> {
+        instructions::add_catalog_cards(ctx, entries)
+    }
+
+    pub fn set_catalog_card_retired(
+        ctx: Context<UpdateCardCatalog>,
+        card_id: u16,
+        retired: bool,
+    ) -> Result<()> {
+        instructions::set_catalog_card_retired(ctx, card_id, retired)
+    }
+
+    pub fn initialize_card_page(ctx: Context<InitializeCardPage>, page: u8) -> Result<()> {
+        instructions::initialize_card_page(ctx, page)
+    }
+
+    pub fn move_cards_to_page(
+        ctx: Context<MoveCardsPage>,
+        page: u8,
+        card_serials: Vec<u64>,
+    ) -> Result<()> {
+        instructions::move_cards_to_page(ctx, page, card_serials)
+    }
+
+    pub fn move_cards_from_page(
+        ctx: Context<MoveCardsPage>,
+        page: u8,
+        card_serials: Vec<u64>,
+    ) -> Result<()> {
+        instructions::move_cards_from_page(ctx, page, card_serials)
+    }
+
+    pub fn migrate_card_serials(ctx: Context<MigrateCardSerials>) -> Result<()> {
+        instructions::migrate_card_serials(ctx)
+    }
+
+    pub fn migrate_player_to_zero_copy(ctx: Context<MigratePlayerToZeroCopy>) -> Result<()> {
+        instructions::migrate_player_to_zero_copy(ctx)
+    }
+
+    pub fn recycle_cards_commit(
+        ctx: Context<RecycleCardsCommit>,
+        card_serials: Vec<u64>,
+    ) -> Result<()> {
+        instructions::recycle_cards_commit(ctx, card_serials)
+    }
+
+    pub fn recycle_cards_settle(ctx: Context<RecycleCardsSettle>) -> Result<()> {
+        instructions::recycle_cards_settle(ctx)
+    }
+
+    pub fn fuse_cards_commit(ctx: Context<FuseCardsCommit>, card_serials: Vec<u64>) -> Result<()> {
+        instructions::fuse_cards_commit(ctx, card_serials)
+    }
+
+    pub fn fuse_cards_settle(ctx: Context<FuseCardsSettle>) -> Result<()> {
+        instructions::fuse_cards_settle(ctx)
+    }
+
+    pub fn craft_commit(ctx: Context<CraftCommit>, target: CraftTarget) -> Result<()> {
+        instructions::craft_commit(ctx, target)
+    }
+
+    pub fn craft_card_settle(ctx: Context<CraftCardSettle>) -> Result<()> {
+        instructions::craft_card_settle(ctx)
+    }
+
+    pub fn initialize_buyback(ctx: Context<InitializeBuyback>) -> Result<()> {
+        instructions::initialize_buyback(ctx)
+    }
+
+    pub fn update_buyback(
+        ctx: Context<UpdateBuyback>,
+        prices: [u64; RARITY_COUNT],
+        fee_share: u8,
+    ) -> Result<()> {
+        instructions::update_buyback(ctx, prices, fee_share)
+    }
+
+    pub fn sell_card_to_protocol(ctx: Context<SellCardToProtocol>, card_serial: u64) -> Result<()> {
+        instructions::sell_card_to_protocol(ctx, card_serial)
+    }
+
+    pub fn claim_referral_rewards(ctx: Context<ClaimReferralRewards>) -> Result<()> {
+        instructions::claim_referral_rewards(ctx)
+    }
+
+    pub fn cancel_pending_action(ctx: Context<CancelPendingAction>) -> Result<()> {
+        instructions::cancel_pending_action(ctx)
+    }
+}
@@ -0,0 +1,464 @@
+use crate::{
+    constants::*,
+    errors::PonzimonError,
+    instructions::{RARITY_COUNT, RECYCLE_ODDS_LEN},
+};
+use anchor_lang::prelude::*;
+
+/// ────────────────────────────────────────────────────────────────────────────
+/// GLOBAL STATE
+/// ────────────────────────────────────────────────────────────────────────────
+#[account]
+#[derive(Default)]
+pub struct GlobalState {
+    pub authority: Pubkey,
+    pub token_mint: Pubkey,
+    pub fees_wallet: Pubkey,
+
+    pub total_supply: u64,
+    pub burned_tokens: u64,
+    pub cumulative_rewards: u64,
+    pub start_slot: u64,
+    pub reward_rate: u64,
+    pub acc_tokens_per_hashpower: u128,
+    pub last_reward_slot: u64,
+
+    pub burn_rate: u8,    // Percent of token fees burned
+    pub referral_fee: u8, // Percent of non-burned booster fees owed to the referrer
+    pub production_enabled: bool,
+    pub cooldown_slots: u64, // Unused; superseded by the upgrade and claim cooldowns
+    pub dust_threshold_divisor: u64,
+
+    pub initial_farm_purchase_fee_lamports: u64,
+    pub booster_pack_cost_microtokens: u64,
+    pub gamble_fee_lamports: u64,
+
+    pub total_berries: u64,
+    pub total_hashpower: u64,
+    pub total_global_gambles: u64,
+    pub total_global_gamble_wins: u64,
+    pub total_booster_packs_opened: u64,
+    pub total_card_recycling_attempts: u64,
+    pub total_successful_card_recycling: u64,
+
+    // Staking pool
+    pub total_staked_tokens: u64,
+    pub staking_lockup_slots: u64,
+    pub acc_sol_rewards_per_token: u128,
+    pub acc_token_rewards_per_token: u128,
+    pub last_staking_reward_slot: u64,
+    pub token_reward_rate: u64,
+    pub total_sol_deposited: u64,
+    pub reward_rate_multiplier: u64,
+    pub last_rate_update_slot: u64,
+
+    pub rewards_vault: Pubkey,
+
+    // Booster pity
+    pub pity_rarity: u8,
+    pub pity_threshold: u8, // 0 disables pity
+
+    /// Recycle upgrade chance in basis points, indexed by source rarity
+    pub recycle_upgrade_odds_bps: [u16; RECYCLE_ODDS_LEN],
+
+    /// Serial the next minted card gets; 0 is reserved for unmigrated cards
+    pub next_card_serial: u64,
+
+    // Shards
+    pub discard_shards: [u16; RARITY_COUNT],
+    pub craft_booster_shards: u16,
+    pub craft_card_shards: [u16; RARITY_COUNT],
+
+    // Cooldowns, 0 disables
+    pub upgrade_cooldown_slots: u32,
+    pub claim_cooldown_slots: u32,
+
+    // Referral commissions in percent, per kind of payment
+    pub referral_fee_initial_farm: u8,
+    pub referral_fee_upgrade: u8,
+
+    /// False on a GlobalState created before recycle_upgrade_odds_bps existed,
+    /// whose table still holds zeros; see recycle_upgrade_odds
+    pub recycle_odds_initialized: bool,
+
+    pub padding: [u8; 1],
+}
+
+impl GlobalState {
+    pub const SIZE: usize = 8 /* discriminator */
+        + 32 + 32 + 32          /* authority + mint + fees_wallet */
+        + 8 + 8                 /* total_supply + burned_tokens */
+        + 8 + 8                 /* cumulative_rewards + start_slot */
+        + 8 + 16 + 8            /* reward_rate + acc_tokens_per_hashpower + last_reward_slot */
+        + 1 + 1 + 1 + 8 + 8     /* burn_rate + referral_fee + prod + cooldown_slots + dust_divisor */
+        + 8 + 8 + 8             /* initial_farm_purchase_fee_lamports + booster_pack_cost_microtokens + gamble_fee_lamports */
+        + 8 + 8                 /* total_berries + total_hashpower */
+        + 8 + 8                 /* total_global_gambles + total_global_gamble_wins */
+        + 8 + 8 + 8             /* total_booster_packs_opened + total_card_recycling_attempts + total_successful_card_recycling */
+        + 8 + 8 + 16 + 16 + 8 + 8 + 8 /* staking: total_staked_tokens + staking_lockup_slots + acc_sol_rewards_per_token + acc_token_rewards_per_token + last_staking_reward_slot + token_reward_rate + total_sol_deposited */
+        + 8 + 8                 /* reward_rate_multiplier + last_rate_update_slot */
+        + 32                    /* rewards_vault */
+        + 1 + 1                 /* pity_rarity + pity_threshold */
+        + 2 * RECYCLE_ODDS_LEN  /* recycle_upgrade_odds_bps */
+        + 8                     /* next_card_serial */
+        + 2 * RARITY_COUNT + 2 + 2 * RARITY_COUNT /* discard_shards + craft_booster_shards + craft_card_shards */
+        + 4 + 4                 /* upgrade_cooldown_slots + claim_cooldown_slots */
+        + 1 + 1                 /* referral_fee_initial_farm + referral_fee_upgrade */
+        + 1                     /* recycle_odds_initialized */
+        + 1; /* padding for future expansion */
+
+    /// The recycle upgrade odds in effect: the stored table once it has been
+    /// initialized, the defaults before that.
+    pub fn recycle_upgrade_odds(&self) -> [u16; RECYCLE_ODDS_LEN] {
+        if self.recycle_odds_initialized {
+            self.recycle_upgrade_odds_bps
+        } else {
+            [ponzimon_math::DEFAULT_UPGRADE_ODDS_BPS; RECYCLE_ODDS_LEN]
+        }
+    }
+}
+
+/// ────────────────────────────────────────────────────────────────────────────
+/// CARDS
+/// ────────────────────────────────────────────────────────────────────────────
+#[zero_copy(unsafe)]
+#[repr(C, packed)]
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Card {
+    pub id: u16,
+    pub rarity: u8,
+    pub hashpower: u16,
+    pub berry_consumption: u8,
+    pub serial: u64, // 0 until migrate_card_serials has run
+}
+
+// Borsh for CardPage. Written by hand because the derive borrows fields, which
+// a packed struct doesn't allow.
+impl AnchorSerialize for Card {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let Card {
+            id,
+            rarity,
+            hashpower,
+            berry_consumption,
+            serial,
+        } = *self;
+        id.serialize(writer)?;
+        rarity.serialize(writer)?;
+        hashpower.serialize(writer)?;
+        berry_consumption.serialize(writer)?;
+        serial.serialize(writer)
+    }
+}
+
+impl AnchorDeserialize for Card {
+    fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+        Ok(Card {
+            id: u16::deserialize_reader(reader)?,
+            rarity: u8::deserialize_reader(reader)?,
+            hashpower: u16::deserialize_reader(reader)?,
+            berry_consumption: u8::deserialize_reader(reader)?,
+            serial: u64::deserialize_reader(reader)?,
+        })
+    }
+}
+
+#[zero_copy(unsafe)]
+#[repr(C, packed)]
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct Farm {
+    pub farm_type: u8,
+    pub total_cards: u8,
+    pub berry_capacity: u64,
+}
+
+/// Randomized action committed by the player and settled a few slots later.
+/// Recycle and Fusion record positions in `Player.cards`, so no card may be
+/// removed or moved while one is pending.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PendingRandomAction {
+    #[default]
+    None,
+    Booster,
+    Recycle {
+        card_indices: [u8; 128],
+        card_count: u8,
+    },
+    Fusion {
+        card_indices: [u8; 128],
+        card_count: u8,
+    },
+    Craft {
+        rarity: u8,
+        cost: u64, // Shards paid, refunded if the rarity can't be drawn at settle
+    },
+}
+
+/// Borsh size of the largest PendingRandomAction (tag + 128 indices + count).
+pub const PENDING_ACTION_SIZE: usize = 1 + 128 + 1;
+/// Borsh size of an Option<Pubkey> (tag + key).
+pub const REFERRER_SIZE: usize = 1 + 32;
+
+/// ────────────────────────────────────────────────────────────────────────────
+/// PLAYER
+/// ────────────────────────────────────────────────────────────────────────────
+/// Zero-copy and packed: fields sit back to back in declaration order, which
+/// the frontend's decodePlayerData relies on. The referrer and pending action
+/// are kept as their Borsh encodings; use the accessors below.
+#[account(zero_copy(unsafe))]
+#[repr(C, packed)]
+pub struct Player {
+    pub owner: Pubkey,
+    pub farm: Farm,
+    pub cards: [Card; MAX_CARDS_PER_PLAYER as usize],
+    pub card_count: u8,
+    pub staked_cards_bitset: u128,
+    pub berries: u64,
+    pub total_hashpower: u64,
+    pub referrer: [u8; REFERRER_SIZE],
+    pub last_acc_tokens_per_hashpower: u128,
+    pub last_claim_slot: u64,
+    pub last_upgrade_slot: u64,
+    pub total_rewards: u64,
+    pub total_gambles: u64,
+    pub total_gamble_wins: u64,
+    pub pending_action: [u8; PENDING_ACTION_SIZE],
+    pub commit_slot: u64,
+    pub total_earnings_for_referrer: u64,
+    pub total_booster_packs_opened: u64,
+    pub total_cards_recycled: u64,
+    pub successful_card_recycling: u64,
+    pub total_sol_spent: u64,
+    pub total_tokens_spent: u64,
+    pub staked_tokens: u64,
+    pub last_stake_slot: u64,
+    pub last_acc_sol_rewards_per_token: u128,
+    pub last_acc_token_rewards_per_token: u128,
+    pub claimed_token_rewards: u64,
+    pub boosters_without_pity: u8,
+    pub auto_stake_booster: u8, // Non-zero: settle_open_booster stakes the drawn cards
+    pub locked_cards_bitset: u128,
+    pub shards: u64,
+    pub accrued_rewards: u64, // Settled inside the claim cooldown, paid after it
+    pub total_sol_earned_for_referrer: u64,
+    pub padding: [u8; 23],
+}
+
+impl Player {
+    pub fn pending_action(&self) -> PendingRandomAction {
+        PendingRandomAction::deserialize(&mut &self.pending_action[..]).unwrap_or_default()
+    }
+
+    pub fn set_pending_action(&mut self, action: PendingRandomAction) {
+        let mut encoded = [0u8; PENDING_ACTION_SIZE];
+        // Every variant fits PENDING_ACTION_SIZE, so this can't fail
+        action.serialize(&mut &mut encoded[..]).unwrap();
+        self.pending_action = encoded;
+    }
+
+    pub fn referrer(&self) -> Option<Pubkey> {
+        Option::<Pubkey>::deserialize(&mut &self.referrer[..]).unwrap_or(None)
+    }
+
+    pub fn set_referrer(&mut self, referrer: Option<Pubkey>) {
+        let mut encoded = [0u8; REFERRER_SIZE];
+        referrer.serialize(&mut &mut encoded[..]).unwrap();
+        self.referrer = encoded;
+    }
+
+    pub fn is_card_staked(&self, card_index: u8) -> bool {
+        card_index < 128 && self.staked_cards_bitset & (1u128 << card_index) != 0
+    }
+
+    pub fn is_card_locked(&self, card_index: u8) -> bool {
+        card_index < 128 && self.locked_cards_bitset & (1u128 << card_index) != 0
+    }
+
+    /// True if the card is committed to the pending recycle or fusion.
+    pub fn is_card_being_recycled(&self, card_index: u8) -> bool {
+        match self.pending_action() {
+            PendingRandomAction::Recycle {
+                card_indices,
+                card_count,
+            }
+            | PendingRandomAction::Fusion {
+                card_indices,
+                card_count,
+            } => card_indices[..card_count as usize].contains(&card_index),
+            _ => false,
+        }
+    }
+
+    pub fn set_card_locked(&mut self, card_index: u8, locked: bool) {
+        let mask = 1u128 << card_index;
+        if locked {
+            self.locked_cards_bitset |= mask;
+        } else {
+            self.locked_cards_bitset &= !mask;
+        }
+    }
+
+    pub fn stake_card(&mut self, card_index: u8) -> Result<()> {
+        require!(
+            card_index < self.card_count,
+            PonzimonError::InvalidCardIndex
+        );
+        self.staked_cards_bitset |= 1u128 << card_index;
+        Ok(())
+    }
+
+    pub fn unstake_card(&mut self, card_index: u8) -> Result<()> {
+        require!(
+            card_index < self.card_count,
+            PonzimonError::InvalidCardIndex
+        );
+        self.staked_cards_bitset &= !(1u128 << card_index);
+        Ok(())
+    }
+
+    pub fn count_staked_cards(&self) -> u8 {
+        let staked_cards_bitset = self.staked_cards_bitset;
+        staked_cards_bitset.count_ones() as u8
+    }
+
+    pub fn add_card(&mut self, card: Card) -> Result<()> {
+        require!(
+            self.card_count < MAX_CARDS_PER_PLAYER,
+            PonzimonError::MachineCapacityExceeded
+        );
+        self.cards[self.card_count as usize] = card;
+        self.card_count += 1;
+        Ok(())
+    }
+
+    /// Removes the cards at `indices` in one pass. The remaining cards close
+    /// ranks in order, and their staked and locked bits move with them.
+    pub fn batch_remove_cards(&mut self, indices: &[u8]) -> Result<()> {
+        let mut removed = 0u128;
+        for &index in indices {
+            require!(index < self.card_count, PonzimonError::InvalidCardIndex);
+            removed |= 1u128 << index;
+        }
+
+        let mut kept = 0usize;
+        let mut staked = 0u128;
+        let mut locked = 0u128;
+        for index in 0..self.card_count as usize {
+            if removed & (1u128 << index) != 0 {
+                continue;
+            }
+            if self.is_card_staked(index as u8) {
+                staked |= 1u128 << kept;
+            }
+            if self.is_card_locked(index as u8) {
+                locked |= 1u128 << kept;
+            }
+            self.cards[kept] = self.cards[index];
+            kept += 1;
+        }
+        for card in self.cards[kept..self.card_count as usize].iter_mut() {
+            *card = Card::default();
+        }
+
+        self.card_count = kept as u8;
+        self.staked_cards_bitset = staked;
+        self.locked_cards_bitset = locked;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    fn card(serial: u64) -> Card {
+        Card {
+            serial,
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn global_state_size_matches_borsh() {
+        let encoded = GlobalState::default().try_to_vec().unwrap();
+        assert_eq!(GlobalState::SIZE, 8 + encoded.len());
+    }
+
+    #[test]
+    fn recycle_odds_read_verbatim_once_initialized() {
+        let mut gs = GlobalState::default();
+        assert_eq!(
+            gs.recycle_upgrade_odds(),
+            [ponzimon_math::DEFAULT_UPGRADE_ODDS_BPS; RECYCLE_ODDS_LEN]
+        );
+
+        // An initialized all-zero table disables upgrades instead of falling back
+        gs.recycle_odds_initialized = true;
+        assert_eq!(gs.recycle_upgrade_odds(), [0; RECYCLE_ODDS_LEN]);
+    }
+
+    #[test]
+    fn pending_action_roundtrips() {
+        let mut player = Player::zeroed();
+        assert_eq!(player.pending_action(), PendingRandomAction::None);
+
+        let recycle = PendingRandomAction::Recycle {
+            card_indices: [127; 128],
+            card_count: 128,
+        };
+        player.set_pending_action(recycle);
+        assert_eq!(player.pending_action(), recycle);
+
+        let craft = PendingRandomAction::Craft {
+            rarity: MEGA_RARE,
+            cost: u64::MAX,
+        };
+        player.set_pending_action(craft);
+        assert_eq!(player.pending_action(), craft);
+    }
+
+    #[test]
+    fn referrer_roundtrips() {
+        let mut player = Player::zeroed();
+        assert_eq!(player.referrer(), None);
+
+        let referrer = Pubkey::new_unique();
+        player.set_referrer(Some(referrer));
+        assert_eq!(player.referrer(), Some(referrer));
+        player.set_referrer(None);
+        assert_eq!(player.referrer(), None);
+    }
+
+    #[test]
+    fn batch_remove_cards_moves_bits_with_cards() {
+        let mut player = Player::zeroed();
+        for serial in 1..=5 {
+            player.add_card(card(serial)).unwrap();
+        }
+        player.stake_card(1).unwrap();
+        player.stake_card(4).unwrap();
+        player.set_card_locked(3, true);
+
+        player.batch_remove_cards(&[0, 2]).unwrap();
+
+        let serials: Vec<u64> = player.cards[..player.card_count as usize]
+            .iter()
+            .map(|card| card.serial)
+            .collect();
+        assert_eq!(serials, [2, 4, 5]);
+        assert!(player.is_card_staked(0) && player.is_card_staked(2));
+        assert!(!player.is_card_staked(1));
+        assert!(player.is_card_locked(1));
+        assert_eq!(player.count_staked_cards(), 2);
+        assert_eq!(player.cards[3], Card::default());
+    }
+
+    #[test]
+    fn batch_remove_cards_rejects_out_of_range() {
+        let mut player = Player::zeroed();
+        player.add_card(card(1)).unwrap();
+        assert!(player.batch_remove_cards(&[1]).is_err());
+        assert_eq!(player.card_count, 1);
+    }
+}
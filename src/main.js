@@ -3,9 +3,13 @@ import './style.css'
 import 'uno.css'
 import App from './App.vue'
 import { createPinia } from 'pinia'
+import { initGameMath } from './utils/gameMath.js'
 
 const pinia = createPinia()
 
 const app = createApp(App)  
 app.use(pinia)
-app.mount('#app')
+// Mount even if the optional wasm build is missing or fails to load
+initGameMath()
+  .catch((error) => console.error('Failed to load game math:', error))
+  .finally(() => app.mount('#app'))
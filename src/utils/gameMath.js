@@ -0,0 +1,95 @@
+/**
+ * Game math compiled from the program's own Rust code (crates/ponzimon-wasm).
+ * The wasm build is opt-in: run `pnpm build:wasm` (needs wasm-pack) to get
+ * it. A glob import resolves to nothing when the package hasn't been built,
+ * so the app still builds and runs without it; the helpers below then throw.
+ */
+const wasmPackage = import.meta.glob('../../crates/ponzimon-wasm/pkg/ponzimon_wasm.js')
+
+let wasm = null
+
+/**
+ * Loads the wasm build if present. Must settle before any other export is
+ * called (main.js waits for it before mounting the app).
+ * @returns {Promise<boolean>} whether the game math is available
+ */
+export async function initGameMath() {
+  const load = Object.values(wasmPackage)[0]
+  if (!load) {
+    console.warn('Game math wasm is not built; run `pnpm build:wasm` to enable it.')
+    return false
+  }
+  const module = await load()
+  await module.default()
+  wasm = module
+  return true
+}
+
+function gameMath() {
+  if (!wasm) {
+    throw new Error('Game math wasm is not loaded; run `pnpm build:wasm` and reload.')
+  }
+  return wasm
+}
+
+/** Booster slot odds in per-mille, indexed by rarity. */
+export const boosterRarityPerMille = () => gameMath().boosterRarityPerMille()
+
+/** Recycle upgrade odds in bps for a rarity, given GlobalState's table. */
+export const recycleUpgradeOddsBps = (upgradeOddsBps, rarity) =>
+  gameMath().recycleUpgradeOddsBps(upgradeOddsBps, rarity)
+
+/**
+ * Builds a RewardPool from a decoded GlobalState (decodeGlobalState output).
+ * @param {object} globalState - decoded GlobalState with decimal-string fields
+ * @returns {RewardPool}
+ */
+export function rewardPoolFromGlobalState(globalState) {
+  return new (gameMath().RewardPool)(
+    BigInt(globalState.start_slot),
+    BigInt(globalState.last_reward_slot),
+    BigInt(globalState.reward_rate),
+    BigInt(globalState.total_hashpower),
+    BigInt(globalState.total_supply),
+    BigInt(globalState.cumulative_rewards),
+    BigInt(globalState.burned_tokens),
+    BigInt(globalState.dust_threshold_divisor),
+    String(globalState.acc_tokens_per_hashpower)
+  )
+}
+
+/**
+ * Rewards a claim would pay at `slot`: advances the pool with update_pool,
+ * then applies the settle_and_mint_rewards formula, including the rewards
+ * the claim cooldown has held back in Player.accrued_rewards.
+ * @returns {bigint} raw token amount
+ */
+export function pendingRewardsAt(globalState, player, slot) {
+  const pool = rewardPoolFromGlobalState(globalState)
+  try {
+    pool.update(BigInt(slot))
+    return pool.pendingRewards(
+      BigInt(player.totalHashpower || '0'),
+      String(player.lastAccTokensPerHashpower || '0'),
+      BigInt(player.accruedRewards || '0')
+    )
+  } finally {
+    pool.free()
+  }
+}
+
+/**
+ * Runs `fn` with the tier table decoded from the FarmConfig account's raw
+ * data, freeing the wasm object afterwards.
+ * @param {Uint8Array} farmConfigData - FarmConfig account data
+ * @param {(tiers: object) => T} fn
+ * @returns {T}
+ */
+export function withFarmTiers(farmConfigData, fn) {
+  const tiers = gameMath().FarmTiers.fromAccountData(farmConfigData)
+  try {
+    return fn(tiers)
+  } finally {
+    tiers.free()
+  }
+}
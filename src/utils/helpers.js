@@ -1,4 +1,5 @@
 import { STORAGE_KEYS } from './constants.js'
+import { withFarmTiers } from './gameMath.js'
 import { PublicKey } from '@solana/web3.js'
 /**
  * 本地存储工具类
@@ -387,20 +388,32 @@ export async function copyToClipboard(text) {
 }
 
 /**
- * 根据农场类型获取农场槽位数
- * @param {number} farmType - 农场类型 (1-10)
+ * 根据农场类型获取农场槽位数（来自链上 FarmConfig 账户）
+ * @param {Uint8Array|null} farmConfigData - FarmConfig 账户原始数据
+ * @param {number} farmType - 农场类型
  * @returns {number} 农场槽位数
  */
-export function getFarmSlots(farmType) {
-  // 映射农场类型到槽位数: [2, 4, 7, 10, 13, 16, 19, 22, 25, 25]
-  const farmSlots = [2, 4, 7, 10, 13, 16, 19, 22, 25, 25]
-  
-  // 确保farmType在有效范围内
-  if (farmType < 1 || farmType > 10) {
-    console.warn(`Invalid farm type: ${farmType}. Expected 1-10.`)
-    return 2 // 默认返回最小槽位数
+export function getFarmSlots(farmConfigData, farmType) {
+  if (!farmConfigData) {
+    console.warn('FarmConfig account not loaded, farm slots unknown.')
+    return 0
   }
-  
-  // farmType从1开始，数组索引从0开始
-  return farmSlots[farmType - 1]
-}
\ No newline at end of file
+
+  try {
+    return withFarmTiers(farmConfigData, (tiers) => {
+      const slots = tiers.totalCards(farmType)
+
+      // 确保farmType在有效范围内
+      if (slots === undefined) {
+        console.warn(`Invalid farm type: ${farmType}. Expected 0-${tiers.count - 1}.`)
+        return 0
+      }
+
+      return slots
+    })
+  } catch (error) {
+    // 模板渲染中调用，wasm 未构建时不能抛出
+    console.warn('Failed to read farm slots:', error.message)
+    return 0
+  }
+}
@@ -17,6 +17,7 @@ import {
 } from '@solana/spl-token'
 import bs58 from 'bs58'
 import { Buffer } from 'node:buffer';
+import { pendingRewardsAt } from './gameMath.js'
 
 // Query GlobalState function (standalone, not part of SolanaWalletTools)
 export async function queryGlobalState(config) {
@@ -98,6 +99,12 @@ const createComputeBudgetInstructions = () => {
   return instructions
 }
 
+// Size of the zero-copy Player struct (without the discriminator) and the
+// offset of Player.accrued_rewards in it. ponzimon-client's tests check both
+// against the program's Player, so update them together with state.rs.
+const PLAYER_ACCOUNT_SIZE = 2263
+const PLAYER_ACCRUED_REWARDS_OFFSET = 2224
+
 // Account decoder for user data
 class AccountDecoder {
   constructor(connection) {
@@ -241,16 +248,31 @@ class AccountDecoder {
     card.berry_consumption = berryConsumption.value
     currentOffset = berryConsumption.nextOffset
 
+    // serial (u64 - 8 bytes)
+    const serial = this.decodeU64(buffer, currentOffset)
+    card.serial = serial.value
+    currentOffset = serial.nextOffset
+
     return {
       value: card,
       nextOffset: currentOffset
     }
   }
 
-  // Decode complete player data
+  // Decode complete player data. Player is a zero-copy account: fields sit
+  // back to back in declaration order with no Borsh length prefixes, and the
+  // referrer and pending action are stored as fixed-width Pod encodings.
+  // Accounts still in the Borsh layout must go through
+  // migrate_player_to_zero_copy first.
   async decodePlayerData(accountData) {
     const player = {}
     const discriminatorLength = 8
+    if (accountData.length !== discriminatorLength + PLAYER_ACCOUNT_SIZE) {
+      throw new Error(
+        `Player account is ${accountData.length} bytes, expected ${discriminatorLength + PLAYER_ACCOUNT_SIZE}; ` +
+        'it may still need migrate_player_to_zero_copy'
+      )
+    }
     const dataWithoutDiscriminator = accountData.slice(discriminatorLength)
     let currentOffset = 0
 
@@ -264,7 +286,7 @@ class AccountDecoder {
     player.farm = farm.value
     currentOffset = farm.nextOffset
 
-    // 3. cards (128 cards)
+    // 3. cards (128 cards, 14 bytes each)
     const cards = []
     for (let i = 0; i < 128; i++) {
       const card = this.decodeCard(dataWithoutDiscriminator, currentOffset)
@@ -293,10 +315,10 @@ class AccountDecoder {
     player.total_hashpower = totalHashpower.value
     currentOffset = totalHashpower.nextOffset
 
-    // 8. referrer (option pubkey)
+    // 8. referrer (Pod option pubkey - tag byte + 32 bytes, always both)
     const referrer = this.decodeOption(dataWithoutDiscriminator, currentOffset, this.decodePubkey)
     player.referrer = referrer.value
-    currentOffset = referrer.nextOffset
+    currentOffset += 1 + 32
 
     // 9. last_acc_tokens_per_hashpower (u128 - 16 bytes)
     const lastAccTokensPerHashpower = this.decodeU128(dataWithoutDiscriminator, currentOffset)
@@ -318,7 +340,14 @@ class AccountDecoder {
     player.total_rewards = totalRewards.value
     currentOffset = totalRewards.nextOffset
 
-    // Return all decoded data (we have the essential fields for reward calculation now)
+    // 13. accrued_rewards (u64 - 8 bytes), held back by the claim cooldown and
+    // paid with the next settlement after it. The fields in between (gamble
+    // and spending stats, pending action, card locks, shards) aren't used here.
+    currentOffset = PLAYER_ACCRUED_REWARDS_OFFSET
+    const accruedRewards = this.decodeU64(dataWithoutDiscriminator, currentOffset)
+    player.accrued_rewards = accruedRewards.value
+    currentOffset = accruedRewards.nextOffset
+
     return player
   }
 
@@ -584,6 +613,33 @@ export class SolanaWalletTools {
     )
     this.rewardsVault = rewardsVault
 
+    const [buybackConfig] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("buyback_config"),
+        this.tokenMint.toBuffer()
+      ],
+      this.programId
+    )
+    this.buybackConfig = buybackConfig
+
+    const [buybackVault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("buyback_vault"),
+        this.tokenMint.toBuffer()
+      ],
+      this.programId
+    )
+    this.buybackVault = buybackVault
+
+    const [farmConfig] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("farm_config"),
+        this.tokenMint.toBuffer()
+      ],
+      this.programId
+    )
+    this.farmConfig = farmConfig
+
     // Initialize token accounts asynchronously
     this.initialized = false
     this.initializeTokenAccounts()
@@ -604,18 +660,6 @@ export class SolanaWalletTools {
         this.feesWallet
       )
 
-      // Initialize referrer token account (may be updated later)
-      try {
-        this.referrerTokenAccount = await getAssociatedTokenAddress(
-          this.tokenMint,
-          this.referrerWallet
-        )
-      } catch (error) {
-        console.warn('Failed to initialize referrer token account, will retry after fetching account info:', error)
-        // Use fees wallet as fallback
-        this.referrerTokenAccount = this.feesTokenAccount
-      }
-
       this.initialized = true
     } catch (error) {
       console.error('Failed to initialize token accounts:', error)
@@ -1072,9 +1116,59 @@ export class SolanaWalletTools {
     })
   }
 
+  // referrer_wallet and referral_vault metas for fee-paying instructions.
+  // Commissions go to the referrer's escrow vault, so the referrer's own token
+  // account is never needed; Anchor reads the program id as an omitted account.
+  async referralAccountMetas() {
+    if (this.playerReferrer === undefined) {
+      await this.getUserAccountInfo()
+    }
+    if (!this.playerReferrer) {
+      return [
+        { pubkey: this.programId, isSigner: false, isWritable: false }, // referrer_wallet
+        { pubkey: this.programId, isSigner: false, isWritable: false }  // referral_vault
+      ]
+    }
+    const [referralVault] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("referral_vault"),
+        this.tokenMint.toBuffer(),
+        this.playerReferrer.toBuffer()
+      ],
+      this.programId
+    )
+    return [
+      { pubkey: this.playerReferrer, isSigner: false, isWritable: false }, // referrer_wallet
+      { pubkey: referralVault, isSigner: false, isWritable: true }         // referral_vault
+    ]
+  }
+
+  // buyback_config and buyback_vault metas for open_booster_commit. Before
+  // initialize_buyback has run both are omitted and the program applies a
+  // 0% buyback share.
+  async buybackAccountMetas() {
+    if (this.buybackInitialized === undefined) {
+      const config = await this.connection.getAccountInfo(this.buybackConfig)
+      this.buybackInitialized = config !== null
+    }
+    if (!this.buybackInitialized) {
+      return [
+        { pubkey: this.programId, isSigner: false, isWritable: false }, // buyback_config
+        { pubkey: this.programId, isSigner: false, isWritable: false }  // buyback_vault
+      ]
+    }
+    return [
+      { pubkey: this.buybackConfig, isSigner: false, isWritable: false }, // buyback_config
+      { pubkey: this.buybackVault, isSigner: false, isWritable: true }    // buyback_vault
+    ]
+  }
+
   // Create open booster commit instruction
-  async createOpenBoosterCommitInstruction() {
+  // autoStake lets settlement stake the drawn cards while the farm has room
+  async createOpenBoosterCommitInstruction(autoStake = false) {
     await this.ensureInitialized()
+    const referralAccounts = await this.referralAccountMetas()
+    const buybackAccounts = await this.buybackAccountMetas()
     
     console.log('Creating open booster commit instruction with accounts:', {
       wallet: this.wallet.publicKey.toBase58(),
@@ -1083,7 +1177,8 @@ export class SolanaWalletTools {
       rewardsVault: this.rewardsVault.toBase58(),
       playerTokenAccount: this.playerTokenAccount.toBase58(),
       feesTokenAccount: this.feesTokenAccount.toBase58(),
-      referrerTokenAccount: this.referrerTokenAccount.toBase58(),
+      referrerWallet: referralAccounts[0].pubkey.toBase58(),
+      referralVault: referralAccounts[1].pubkey.toBase58(),
       tokenMint: this.tokenMint.toBase58()
     })
     
@@ -1118,24 +1213,29 @@ export class SolanaWalletTools {
         isSigner: false,
         isWritable: true
       },
+      ...referralAccounts,
+      ...buybackAccounts,
       {
-        pubkey: this.referrerTokenAccount,
+        pubkey: this.tokenMint,
         isSigner: false,
         isWritable: true
       },
       {
-        pubkey: this.tokenMint,
+        pubkey: TOKEN_PROGRAM_ID,
         isSigner: false,
-        isWritable: true
+        isWritable: false
       },
       {
-        pubkey: TOKEN_PROGRAM_ID,
+        pubkey: SystemProgram.programId,
         isSigner: false,
         isWritable: false
       }
     ]
 
-    const instructionData = Buffer.from('07fc87dff2ecf25d', 'hex')
+    const instructionData = Buffer.concat([
+      Buffer.from('07fc87dff2ecf25d', 'hex'),
+      Buffer.from([autoStake ? 1 : 0])
+    ])
 
     return {
       programId: this.programId,
@@ -1198,7 +1298,7 @@ export class SolanaWalletTools {
     }
   }
   
-  async openBooster() {
+  async openBooster(autoStake = false) {
     return await this.withLock(async () => {
       // Check if opening booster is allowed
       await this.canOpenBooster()
@@ -1207,7 +1307,7 @@ export class SolanaWalletTools {
       
       // Step 1: Execute open booster commit
       console.log('Step 1: Executing open booster commit...')
-      await this.executeOpenBoosterCommit()
+      await this.executeOpenBoosterCommit(autoStake)
       
       // Wait a bit between steps
       await new Promise(resolve => setTimeout(resolve, 300)) // 减少等待时间
@@ -1221,10 +1321,10 @@ export class SolanaWalletTools {
   }
   // Execute open booster commit
   // private
-  async executeOpenBoosterCommit() {
+  async executeOpenBoosterCommit(autoStake = false) {
     await this.ensureInitialized()
     const computeBudgetInstructions = createComputeBudgetInstructions()
-    const openBoosterCommitInstruction = await this.createOpenBoosterCommitInstruction()
+    const openBoosterCommitInstruction = await this.createOpenBoosterCommitInstruction(autoStake)
     computeBudgetInstructions.push(openBoosterCommitInstruction)
     return await this.buildAndSendTransaction(computeBudgetInstructions)
   }
@@ -1384,9 +1484,10 @@ export class SolanaWalletTools {
         setTimeout(() => reject(new Error('RPC request timeout')), 15000)
       })
       
-      const accountInfoPromise = this.connection.getAccountInfo(this.playerPDA)
+      // The FarmConfig account holds the authoritative tier table, so fetch it alongside
+      const accountInfoPromise = this.connection.getMultipleAccountsInfo([this.playerPDA, this.farmConfig])
       
-      const accountInfo = await Promise.race([accountInfoPromise, timeoutPromise])
+      const [accountInfo, farmConfigInfo] = await Promise.race([accountInfoPromise, timeoutPromise])
       
       console.log('Account info response:', accountInfo ? 'Account found' : 'Account not found')
       
@@ -1404,16 +1505,9 @@ export class SolanaWalletTools {
       if (playerData.referrer) {
         console.log('Updating referrer wallet to:', playerData.referrer)
         this.referrerWallet = new PublicKey(playerData.referrer)
-        // Reinitialize referrer token account with the new referrer
-        try {
-          this.referrerTokenAccount = await getAssociatedTokenAddress(
-            this.tokenMint,
-            this.referrerWallet
-          )
-        } catch (error) {
-          console.warn('Failed to update referrer token account:', error)
-        }
       }
+      // null (not undefined) once loaded, so referralAccountMetas knows there is no referrer
+      this.playerReferrer = playerData.referrer ? this.referrerWallet : null
 
       // Add staking status to cards based on bitset
       const stakedCardsBitset = BigInt(playerData.staked_cards_bitset)
@@ -1436,6 +1530,7 @@ export class SolanaWalletTools {
         initialized: true,
         owner: playerData.owner,
         farm: playerData.farm,
+        farmConfigData: farmConfigInfo ? farmConfigInfo.data : null,
         cards: cardsWithStakeStatus,
         cardCount: playerData.card_count,
         stakedCardsBitset: playerData.staked_cards_bitset,
@@ -1446,7 +1541,8 @@ export class SolanaWalletTools {
         lastAccTokensPerHashpower: playerData.last_acc_tokens_per_hashpower,
         lastClaimSlot: playerData.last_claim_slot,
         lastUpgradeSlot: playerData.last_upgrade_slot,
-        totalRewards: playerData.total_rewards
+        totalRewards: playerData.total_rewards,
+        accruedRewards: playerData.accrued_rewards
       }
     } catch (error) {
       console.error('Failed to get user account info:', error)
@@ -1860,8 +1956,11 @@ export class SolanaWalletTools {
             { pubkey: this.rewardsVault, isSigner: false, isWritable: true },           // rewards_vault
             { pubkey: this.playerTokenAccount, isSigner: false, isWritable: true },     // player_token_account
             { pubkey: new PublicKey('HiAkAbqMXoNfS6QLpCjvpUKgxufq3q4Z3dgx8EbLBEad'), isSigner: false, isWritable: true }, // fees_token_account
+            ...(await this.referralAccountMetas()),                                     // referrer_wallet, referral_vault
+            { pubkey: this.farmConfig, isSigner: false, isWritable: false },            // farm_config
             { pubkey: this.tokenMint, isSigner: false, isWritable: true },              // token_mint
-            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false }            // token_program
+            { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },           // token_program
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }     // system_program
           ],
           programId: this.programId,
           data: instructionData
@@ -1984,8 +2083,8 @@ export class SolanaWalletTools {
       const playerHashpower = BigInt(accountInfo.totalHashpower || '0')
       const lastAccTokensPerHashpower = BigInt(accountInfo.lastAccTokensPerHashpower || '0')
       const lastClaimSlot = BigInt(accountInfo.lastClaimSlot || globalState.start_slot)
+      const accruedRewards = BigInt(accountInfo.accruedRewards || '0')
       
-      const currentSlotBN = BigInt(currentSlot)
       const globalAccTokensPerHashpower = BigInt(globalState.acc_tokens_per_hashpower)
       const totalSupply = BigInt(globalState.total_supply)
       const burnedTokens = BigInt(globalState.burned_tokens)
@@ -1995,6 +2094,7 @@ export class SolanaWalletTools {
         playerHashpower: playerHashpower.toString(),
         lastAccTokensPerHashpower: lastAccTokensPerHashpower.toString(),
         lastClaimSlot: lastClaimSlot.toString(),
+        accruedRewards: accruedRewards.toString(),
         currentSlot: currentSlot,
         globalAccTokensPerHashpower: globalAccTokensPerHashpower.toString(),
         totalSupply: totalSupply.toString(),
@@ -2002,54 +2102,24 @@ export class SolanaWalletTools {
         cumulativeRewards: cumulativeRewards.toString()
       })
 
-      // Check cooldown (now > player.last_claim_slot)
-      if (currentSlotBN <= lastClaimSlot) {
-        console.log('Current slot <= last claim slot, no pending rewards (cooldown)')
-        return { success: true, rewards: 0 }
-      }
-
-      // Calculate pending using the exact formula from settle_and_mint_rewards
-      // pending_u128 = (player.total_hashpower as u128) * (gs.acc_tokens_per_hashpower - player.last_acc_tokens_per_hashpower) / ACC_SCALE
-      
-      // We need to estimate ACC_SCALE - based on common DeFi patterns, it's likely 10^18 for precision
-      const ACC_SCALE = BigInt('1000000000000000000') // 10^18, common scaling factor
-      
-      const accTokensDifference = globalAccTokensPerHashpower - lastAccTokensPerHashpower
-      
-      if (accTokensDifference <= 0n) {
-        console.log('No accumulator difference, no pending rewards')
-        return { success: true, rewards: 0 }
-      }
-
-      // Calculate pending rewards: (hashpower * acc_difference) / ACC_SCALE
-      let pendingU128 = playerHashpower * accTokensDifference
-      let pending = pendingU128 / ACC_SCALE
-      
-      // Clamp pending to remaining supply (same as in contract)
-      const mintedMinusBurn = cumulativeRewards - burnedTokens
-      const remainingSupply = totalSupply - mintedMinusBurn
-      
-      if (pending > remainingSupply) {
-        pending = remainingSupply
-      }
+      // Same update_pool + pending formula as settle_and_mint_rewards, compiled
+      // from the program's Rust code, so the accumulator is projected to now.
+      // No slot check up front: nothing new accrues before the last claim, but
+      // rewards held back by the claim cooldown are still owed.
+      const pending = pendingRewardsAt(globalState, accountInfo, currentSlot)
 
       // Convert to readable format (6 decimals)
       const pendingReadable = Number(pending) / 1000000
       
       console.log('Pending rewards calculation result:', {
-        accTokensDifference: accTokensDifference.toString(),
-        pendingU128: pendingU128.toString(),
         pendingRaw: pending.toString(),
-        pendingReadable: pendingReadable,
-        remainingSupply: remainingSupply.toString(),
-        ACC_SCALE_used: ACC_SCALE.toString()
+        pendingReadable: pendingReadable
       })
 
       return {
         success: true,
         rewards: Math.max(0, pendingReadable), // Ensure non-negative
-        rewardsRaw: pending, // Raw amount for precise calculations
-        estimatedWithACCScale: ACC_SCALE.toString()
+        rewardsRaw: pending // Raw amount for precise calculations
       }
       
     } catch (error) {